@@ -0,0 +1,39 @@
+use std::cell::RefCell;
+use std::cell::Cell;
+use std::rc::Rc;
+
+use glib::Properties;
+use gtk::glib;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+
+use crate::calendar::Event;
+
+#[derive(Default, Properties, Debug)]
+#[properties[wrapper_type = super::AgendaState]]
+pub struct AgendaState {
+    /// True while the calendars are being read, the events are updated when it turns false
+    #[property(get, set)]
+    is_loading: Cell<bool>,
+    /// Every event from the calendars, recurring ones are not expanded
+    events: RefCell<Rc<Vec<Event>>>
+}
+
+impl AgendaState {
+    pub fn get_events(&self) -> Rc<Vec<Event>> {
+        self.events.borrow().clone()
+    }
+
+    pub fn set_events(&self, new_value: Vec<Event>) {
+        self.events.replace(Rc::new(new_value));
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for AgendaState {
+    const NAME: &'static str = "AgendaState";
+    type Type = super::AgendaState;
+}
+
+#[glib::derived_properties]
+impl ObjectImpl for AgendaState {}
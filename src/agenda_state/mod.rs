@@ -0,0 +1,28 @@
+use glib::Object;
+use gtk::glib::{self, subclass::types::ObjectSubclassIsExt};
+
+use std::rc::Rc;
+
+mod imp;
+
+use crate::calendar::Event;
+
+glib::wrapper!{
+    pub struct AgendaState(ObjectSubclass<imp::AgendaState>);
+}
+
+impl AgendaState {
+    pub fn new() -> Self {
+        Object::builder()
+            .property("is_loading", false)
+            .build()
+    }
+
+    pub fn set_events(&self, new_value: Vec<Event>) {
+        self.imp().set_events(new_value)
+    }
+
+    pub fn get_events(&self) -> Rc<Vec<Event>> {
+        self.imp().get_events()
+    }
+}
@@ -0,0 +1,139 @@
+use gtk::{ Align, DrawingArea, cairo, glib, prelude::* };
+use chrono::{ Local, Timelike };
+
+use std::f64::consts::PI;
+use std::time::Duration;
+
+use crate::clock::ticker::{ Scheduler, SystemTime, Ticker };
+
+// The hands and tick marks are drawn with the css color of the widget (the palette's --dinfo-text),
+// so it follows the theme like the digital clock.
+
+/// How the second hand moves
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SecondHand {
+    /// Jumps once a second
+    Ticking,
+    /// Moves continuously, redraws on every frame
+    Smooth,
+    /// No second hand, redraws once a minute
+    Hidden
+}
+
+impl SecondHand {
+    /// Parses the name used in the config: ticking, smooth or none
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "ticking" => Some(Self::Ticking),
+            "smooth" => Some(Self::Smooth),
+            "none" => Some(Self::Hidden),
+            _ => None
+        }
+    }
+}
+
+/// Draws a hand from the center at `angle` (radians, 0 is 12 o'clock) with an outline for readability
+fn draw_hand(cr: &cairo::Context, color: &gtk::gdk::RGBA, center: (f64, f64), angle: f64, length: f64, width: f64) {
+    let end = (center.0 + angle.sin() * length, center.1 - angle.cos() * length);
+    cr.set_line_cap(cairo::LineCap::Round);
+
+    cr.set_source_rgba(0.0, 0.0, 0.0, 0.4);
+    cr.set_line_width(width + 2.0);
+    cr.move_to(center.0, center.1);
+    cr.line_to(end.0, end.1);
+    let _ = cr.stroke();
+
+    cr.set_source_rgba(color.red() as f64, color.green() as f64, color.blue() as f64, color.alpha() as f64);
+    cr.set_line_width(width);
+    cr.move_to(center.0, center.1);
+    cr.line_to(end.0, end.1);
+    let _ = cr.stroke();
+}
+
+fn draw_clock(area: &DrawingArea, cr: &cairo::Context, width: i32, height: i32, second_hand: SecondHand) {
+    let color = area.color();
+    let center = (width as f64 / 2.0, height as f64 / 2.0);
+    let radius = (width.min(height) as f64 / 2.0) - 4.0;
+
+    // Tick marks, longer and thicker on the hours
+    cr.set_source_rgba(color.red() as f64, color.green() as f64, color.blue() as f64, color.alpha() as f64);
+    cr.set_line_cap(cairo::LineCap::Round);
+    for i in 0..60 {
+        let angle = i as f64 / 60.0 * 2.0 * PI;
+        let (length, line_width) = if i % 5 == 0 { (radius * 0.12, 4.0) } else { (radius * 0.05, 1.5) };
+
+        cr.set_line_width(line_width);
+        cr.move_to(center.0 + angle.sin() * radius, center.1 - angle.cos() * radius);
+        cr.line_to(center.0 + angle.sin() * (radius - length), center.1 - angle.cos() * (radius - length));
+        let _ = cr.stroke();
+    }
+
+    let now = Local::now();
+    let seconds = match second_hand {
+        SecondHand::Smooth => now.second() as f64 + now.nanosecond().min(999_999_999) as f64 / 1e9,
+        _ => now.second() as f64
+    };
+    let minutes = now.minute() as f64 + seconds / 60.0;
+    let hours = (now.hour() % 12) as f64 + minutes / 60.0;
+
+    draw_hand(cr, &color, center, hours / 12.0 * 2.0 * PI, radius * 0.5, 8.0);
+    draw_hand(cr, &color, center, minutes / 60.0 * 2.0 * PI, radius * 0.8, 5.0);
+
+    if second_hand != SecondHand::Hidden {
+        draw_hand(cr, &color, center, seconds / 60.0 * 2.0 * PI, radius * 0.9, 2.0);
+    }
+
+    // Cap over the middle of the hands
+    cr.set_source_rgba(color.red() as f64, color.green() as f64, color.blue() as f64, color.alpha() as f64);
+    cr.arc(center.0, center.1, 7.0, 0.0, 2.0 * PI);
+    let _ = cr.fill();
+}
+
+/// Builds an analog clock face, as an alternative of the digital clock
+pub fn build_analog_clock(second_hand: SecondHand) -> DrawingArea {
+    let clock = {
+        DrawingArea::builder()
+        .content_width(300)
+        .content_height(300)
+        .name("analog_clock")
+        .css_classes(["text", "analog_clock"])
+        .halign(Align::Center)
+        .valign(Align::Center)
+        .build()
+    };
+
+    clock.set_draw_func(move |area, cr, width, height| {
+        draw_clock(area, cr, width, height, second_hand);
+    });
+
+    match second_hand {
+        SecondHand::Smooth => {
+            clock.add_tick_callback(|area, _| {
+                area.queue_draw();
+                glib::ControlFlow::Continue
+            });
+        },
+        SecondHand::Ticking | SecondHand::Hidden => {
+            let period = if second_hand == SecondHand::Ticking {
+                Duration::from_secs(1)
+            } else {
+                Duration::from_secs(60)
+            };
+
+            // The clock is only held while drawing, so the loop stops when it's destroyed
+            let clock_ref = clock.downgrade();
+            glib::spawn_future_local(async move {
+                let ticker = Ticker::new(Scheduler::new(SystemTime::new(), period));
+                loop {
+                    match clock_ref.upgrade() {
+                        Some(clock) => clock.queue_draw(),
+                        None => break
+                    }
+                    ticker.tick().await;
+                }
+            });
+        }
+    }
+
+    clock
+}
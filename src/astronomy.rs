@@ -0,0 +1,341 @@
+use chrono::{ DateTime, Local, NaiveDate, TimeZone, Utc };
+use std::f64::consts::PI;
+use std::fmt;
+use std::time::Duration;
+
+use crate::weather::Cordinates;
+
+// Everything here is computed locally from the cordinates and the date, no network calls.
+// The sun calculations follow the NOAA / "sunrise equation" approximations, which are
+// accurate to about a minute for non polar latitudes. That is plenty for a desktop widget.
+
+/// Julian date of the J2000 epoch
+const J2000: f64 = 2451545.0;
+/// Julian date of the unix epoch
+const UNIX_EPOCH_JD: f64 = 2440587.5;
+/// Axial tilt of the earth in degrees
+const EARTH_TILT: f64 = 23.4397;
+/// Average length of a lunar cycle in days
+const SYNODIC_MONTH: f64 = 29.530588853;
+/// Julian date of a known new moon (2000-01-06 18:14 UTC)
+const KNOWN_NEW_MOON: f64 = 2451550.26;
+
+/// Sun altitude at sunrise and sunset, corrected for refraction and the sun's radius
+const SUNRISE_ALTITUDE: f64 = -0.833;
+const CIVIL_ALTITUDE: f64 = -6.0;
+const NAUTICAL_ALTITUDE: f64 = -12.0;
+/// Golden hour lasts while the sun is between these altitudes
+const GOLDEN_HOUR_LOW: f64 = -4.0;
+const GOLDEN_HOUR_HIGH: f64 = 6.0;
+
+/// The morning and evening time the sun crosses a given altitude.
+/// Both are None if the sun never crosses it that day (polar day or night)
+#[derive(Debug, Clone, Copy)]
+pub struct Crossing {
+    pub morning: Option<DateTime<Local>>,
+    pub evening: Option<DateTime<Local>>
+}
+
+/// Sun events of a single day, in local time
+#[derive(Debug, Clone)]
+pub struct SunTimes {
+    /// The time the sun is the highest on the sky
+    pub noon: DateTime<Local>,
+    pub sunrise: Crossing,
+    /// Civil twilight starts (dawn) and ends (dusk) when the sun is 6° below the horizon
+    pub civil: Crossing,
+    /// Nautical twilight starts (dawn) and ends (dusk) when the sun is 12° below the horizon
+    pub nautical: Crossing,
+    /// The end of the morning golden hour, and the start of the evening one (sun at 6°)
+    pub golden_hour: Crossing,
+    /// The start of the morning golden hour, and the end of the evening one (sun at -4°)
+    pub golden_hour_bounds: Crossing,
+    /// Time between sunrise and sunset, 0 for polar night and 24h for polar day
+    pub day_length: Duration
+}
+
+impl SunTimes {
+    /// Calculates the sun events for a day at the given location
+    pub fn new(loc: &Cordinates, date: NaiveDate) -> Self {
+        let transit = solar_transit(loc, date);
+        let crossing = |altitude: f64| -> Crossing {
+            match hour_angle(loc, transit, altitude) {
+                Some(angle) => Crossing {
+                    morning: Some(jd_to_local(transit - angle / 360.0)),
+                    evening: Some(jd_to_local(transit + angle / 360.0))
+                },
+                None => Crossing { morning: None, evening: None }
+            }
+        };
+
+        let sunrise = crossing(SUNRISE_ALTITUDE);
+
+        let day_length = match (sunrise.morning, sunrise.evening) {
+            (Some(rise), Some(set)) => (set - rise).to_std().unwrap_or_default(),
+            // The sun doesnt cross the horizon, so it's either always up or down
+            _ => {
+                if sun_elevation(loc, jd_to_utc(transit)) > SUNRISE_ALTITUDE {
+                    Duration::from_hours(24)
+                } else {
+                    Duration::ZERO
+                }
+            }
+        };
+
+        SunTimes {
+            noon: jd_to_local(transit),
+            sunrise,
+            civil: crossing(CIVIL_ALTITUDE),
+            nautical: crossing(NAUTICAL_ALTITUDE),
+            golden_hour: crossing(GOLDEN_HOUR_HIGH),
+            golden_hour_bounds: crossing(GOLDEN_HOUR_LOW),
+            day_length
+        }
+    }
+
+    /// Calculates the sun events for today at the given location
+    pub fn today(loc: &Cordinates) -> Self {
+        Self::new(loc, Local::now().date_naive())
+    }
+}
+
+/// The phase of the moon
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MoonPhase {
+    New,
+    WaxingCrescent,
+    FirstQuarter,
+    WaxingGibbous,
+    Full,
+    WaningGibbous,
+    LastQuarter,
+    WaningCrescent
+}
+
+impl fmt::Display for MoonPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::New => "New moon",
+            Self::WaxingCrescent => "Waxing crescent",
+            Self::FirstQuarter => "First quarter",
+            Self::WaxingGibbous => "Waxing gibbous",
+            Self::Full => "Full moon",
+            Self::WaningGibbous => "Waning gibbous",
+            Self::LastQuarter => "Last quarter",
+            Self::WaningCrescent => "Waning crescent",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl MoonPhase {
+    pub fn to_emoji(&self) -> String {
+        match self {
+            Self::New => "🌑".to_string(),
+            Self::WaxingCrescent => "🌒".to_string(),
+            Self::FirstQuarter => "🌓".to_string(),
+            Self::WaxingGibbous => "🌔".to_string(),
+            Self::Full => "🌕".to_string(),
+            Self::WaningGibbous => "🌖".to_string(),
+            Self::LastQuarter => "🌗".to_string(),
+            Self::WaningCrescent => "🌘".to_string(),
+        }
+    }
+}
+
+/// The state of the moon at a given time
+#[derive(Debug, Clone, Copy)]
+pub struct Moon {
+    pub phase: MoonPhase,
+    /// Days since the last new moon
+    pub age: f64,
+    /// Illuminated fraction of the moon, 0-1
+    pub illumination: f64
+}
+
+impl Moon {
+    pub fn new<Tz: TimeZone>(time: DateTime<Tz>) -> Self {
+        let age = (to_julian(time.with_timezone(&Utc)) - KNOWN_NEW_MOON).rem_euclid(SYNODIC_MONTH);
+        let cycle = age / SYNODIC_MONTH;
+
+        let illumination = (1.0 - (2.0 * PI * cycle).cos()) / 2.0;
+
+        // Each phase is centered on its point of the cycle, so "Full" lasts from 7/16 to 9/16
+        let phase = match ((cycle * 8.0).round() as u8) % 8 {
+            0 => MoonPhase::New,
+            1 => MoonPhase::WaxingCrescent,
+            2 => MoonPhase::FirstQuarter,
+            3 => MoonPhase::WaxingGibbous,
+            4 => MoonPhase::Full,
+            5 => MoonPhase::WaningGibbous,
+            6 => MoonPhase::LastQuarter,
+            _ => MoonPhase::WaningCrescent
+        };
+
+        Moon { phase, age, illumination }
+    }
+
+    pub fn now() -> Self {
+        Self::new(Utc::now())
+    }
+}
+
+/// Returns the elevation of the sun above the horizon in degrees at the given time
+pub fn sun_elevation<Tz: TimeZone>(loc: &Cordinates, time: DateTime<Tz>) -> f64 {
+    let jd = to_julian(time.with_timezone(&Utc));
+    let (declination, equation_of_time) = sun_declination_and_eot(jd);
+
+    // Hour angle of the sun: 0 at solar noon, 15° per hour
+    let utc_hours = (jd + 0.5).rem_euclid(1.0) * 24.0;
+    let solar_time = utc_hours + loc.lng / 15.0 + equation_of_time / 60.0;
+    let hour_angle = (solar_time - 12.0) * 15.0;
+
+    let lat = loc.lat.to_radians();
+    let sin_elevation = lat.sin() * declination.sin() + lat.cos() * declination.cos() * hour_angle.to_radians().cos();
+
+    sin_elevation.clamp(-1.0, 1.0).asin().to_degrees()
+}
+
+/// True if the sun is above the horizon at the given location and time
+pub fn is_day<Tz: TimeZone>(loc: &Cordinates, time: DateTime<Tz>) -> bool {
+    sun_elevation(loc, time) > SUNRISE_ALTITUDE
+}
+
+fn to_julian(time: DateTime<Utc>) -> f64 {
+    time.timestamp_millis() as f64 / 86_400_000.0 + UNIX_EPOCH_JD
+}
+
+fn jd_to_utc(jd: f64) -> DateTime<Utc> {
+    let millis = ((jd - UNIX_EPOCH_JD) * 86_400_000.0).round() as i64;
+    DateTime::from_timestamp_millis(millis).unwrap_or_default()
+}
+
+fn jd_to_local(jd: f64) -> DateTime<Local> {
+    jd_to_utc(jd).with_timezone(&Local)
+}
+
+/// Mean anomaly and ecliptic longitude of the sun in degrees, for days since J2000
+fn sun_anomaly_and_longitude(days: f64) -> (f64, f64) {
+    let anomaly = (357.5291 + 0.98560028 * days).rem_euclid(360.0);
+    let m = anomaly.to_radians();
+    let center = 1.9148 * m.sin() + 0.0200 * (2.0 * m).sin() + 0.0003 * (3.0 * m).sin();
+    let longitude = (anomaly + center + 180.0 + 102.9372).rem_euclid(360.0);
+
+    (anomaly, longitude)
+}
+
+/// Declination of the sun (radians) and the equation of time (minutes) at a julian date
+fn sun_declination_and_eot(jd: f64) -> (f64, f64) {
+    let days = jd - J2000;
+    let (anomaly, longitude) = sun_anomaly_and_longitude(days);
+    let declination = (longitude.to_radians().sin() * EARTH_TILT.to_radians().sin()).asin();
+
+    // Difference between the apparent and the mean solar noon, converted to minutes
+    let transit_offset = 0.0053 * anomaly.to_radians().sin() - 0.0069 * (2.0 * longitude.to_radians()).sin();
+    let equation_of_time = -transit_offset * 24.0 * 60.0;
+
+    (declination, equation_of_time)
+}
+
+/// Julian date of the solar noon on the given date
+fn solar_transit(loc: &Cordinates, date: NaiveDate) -> f64 {
+    let noon = date.and_hms_opt(12, 0, 0).expect("12:00:00 is a valid time").and_utc();
+    let days = (to_julian(noon) - J2000 + 0.0008).round();
+
+    // Mean solar noon, west longitudes are negative
+    let mean_noon = days - loc.lng / 360.0;
+    let (anomaly, longitude) = sun_anomaly_and_longitude(mean_noon);
+
+    J2000 + mean_noon + 0.0053 * anomaly.to_radians().sin() - 0.0069 * (2.0 * longitude.to_radians()).sin()
+}
+
+/// The hour angle (degrees) between solar noon and the sun reaching `altitude`.
+/// Returns None if the sun never reaches that altitude on the day of the transit
+fn hour_angle(loc: &Cordinates, transit: f64, altitude: f64) -> Option<f64> {
+    let (declination, _) = sun_declination_and_eot(transit);
+    let lat = loc.lat.to_radians();
+
+    let cos_angle = (altitude.to_radians().sin() - lat.sin() * declination.sin()) / (lat.cos() * declination.cos());
+
+    if (-1.0..=1.0).contains(&cos_angle) {
+        Some(cos_angle.acos().to_degrees())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LONDON: Cordinates = Cordinates { lat: 51.5074, lng: -0.1278 };
+    const NEW_YORK: Cordinates = Cordinates { lat: 40.7128, lng: -74.0060 };
+    const TROMSO: Cordinates = Cordinates { lat: 69.6492, lng: 18.9553 };
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32, s: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, s).unwrap()
+    }
+
+    /// Within 2 minutes of NOAA's solar calculator
+    fn assert_close(time: Option<DateTime<Local>>, expected: DateTime<Utc>) {
+        let difference = (time.unwrap().with_timezone(&Utc) - expected).num_seconds().abs();
+        assert!(difference <= 120, "{:?} is {difference}s off from {expected}", time);
+    }
+
+    #[test]
+    fn sunrise_and_sunset() {
+        let london = SunTimes::new(&LONDON, date(2026, 6, 21));
+        assert_close(london.sunrise.morning, utc(2026, 6, 21, 3, 43, 6));
+        assert_close(london.sunrise.evening, utc(2026, 6, 21, 20, 21, 36));
+
+        let new_york = SunTimes::new(&NEW_YORK, date(2026, 12, 21));
+        assert_close(new_york.sunrise.morning, utc(2026, 12, 21, 12, 16, 36));
+        assert_close(new_york.sunrise.evening, utc(2026, 12, 21, 21, 31, 48));
+        assert!(new_york.day_length > Duration::from_hours(9) && new_york.day_length < Duration::from_hours(10));
+    }
+
+    #[test]
+    fn polar_day_and_night() {
+        let summer = SunTimes::new(&TROMSO, date(2026, 6, 21));
+        assert!(summer.sunrise.morning.is_none() && summer.sunrise.evening.is_none());
+        assert_eq!(summer.day_length, Duration::from_hours(24));
+
+        let winter = SunTimes::new(&TROMSO, date(2026, 12, 21));
+        assert!(winter.sunrise.morning.is_none() && winter.sunrise.evening.is_none());
+        assert_eq!(winter.day_length, Duration::ZERO);
+        // The sun still gets close enough to the horizon for civil twilight
+        assert!(winter.civil.morning.is_some());
+    }
+
+    #[test]
+    fn elevation_and_day() {
+        // 90° - latitude + declination at noon on the solstice
+        let noon = sun_elevation(&LONDON, utc(2026, 6, 21, 12, 2, 0));
+        assert!((noon - 61.9).abs() < 0.5, "{noon}");
+
+        assert!(is_day(&LONDON, utc(2026, 6, 21, 12, 0, 0)));
+        assert!(!is_day(&LONDON, utc(2026, 6, 21, 0, 0, 0)));
+        assert!(is_day(&TROMSO, utc(2026, 6, 21, 23, 0, 0)));
+    }
+
+    #[test]
+    fn moon_phases() {
+        // Full moon on 2026-01-03 10:03 UTC
+        let full = Moon::new(utc(2026, 1, 3, 10, 3, 0));
+        assert_eq!(full.phase, MoonPhase::Full);
+        assert!(full.illumination > 0.99, "{}", full.illumination);
+        assert!((full.age - SYNODIC_MONTH / 2.0).abs() < 0.75, "{}", full.age);
+
+        // New moon on 2026-01-18 19:52 UTC
+        let new = Moon::new(utc(2026, 1, 18, 19, 52, 0));
+        assert_eq!(new.phase, MoonPhase::New);
+        assert!(new.illumination < 0.01, "{}", new.illumination);
+
+        // First quarter on 2026-01-26 04:47 UTC
+        assert_eq!(Moon::new(utc(2026, 1, 26, 4, 47, 0)).phase, MoonPhase::FirstQuarter);
+    }
+}
@@ -0,0 +1,229 @@
+use gtk::{ DrawingArea, cairo, glib, prelude::* };
+use chrono::Utc;
+use upower_dbus::BatteryState;
+
+use std::cell::RefCell;
+use std::pin::pin;
+use std::rc::Rc;
+use std::time::Duration;
+use futures::future;
+
+use crate::ui_elements::format_battery_time;
+use crate::upower::{ UPower, history::{ self, BatteryHistory, HistoryPoint } };
+
+// The main battery's last 24 hours from UPower's history: the charge as a line (0-100%),
+// and the discharge rate as a filled area under it, scaled to the highest rate of the day.
+// Like the pomodoro ring, it's drawn with the css color of the widget.
+
+/// How often the history is read again, UPower only adds a point when something changes anyway
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+const LINE_WIDTH: f64 = 2.0;
+
+/// Where a unix time is on the graph, the left edge is 24 hours ago
+fn time_x(time: i64, width: f64) -> f64 {
+    let span = history::HISTORY_SPAN.as_secs_f64();
+    let ago = (Utc::now().timestamp() - time) as f64;
+
+    (1.0 - ago / span).clamp(0.0, 1.0) * width
+}
+
+/// Maps the points to the area, x from the time and y from the value over `max`
+fn plot(cr: &cairo::Context, points: &[HistoryPoint], width: f64, height: f64, max: f64) {
+    for (i, point) in points.iter().enumerate() {
+        let x = time_x(point.time, width);
+        let y = height - (point.value / max).clamp(0.0, 1.0) * (height - LINE_WIDTH) - LINE_WIDTH / 2.0;
+
+        match i {
+            0 => cr.move_to(x, y),
+            _ => cr.line_to(x, y)
+        }
+    }
+}
+
+fn draw_graph(area: &DrawingArea, cr: &cairo::Context, width: i32, height: i32, history: &BatteryHistory) {
+    let color = area.color();
+    let (r, g, b, a) = (color.red() as f64, color.green() as f64, color.blue() as f64, color.alpha() as f64);
+    let (width, height) = (width as f64, height as f64);
+
+    // Guides at 0, 50 and 100%
+    cr.set_line_width(1.0);
+    cr.set_source_rgba(r, g, b, a * 0.15);
+    for level in [0.0, 0.5, 1.0] {
+        let y = (height - 1.0) * (1.0 - level) + 0.5;
+        cr.move_to(0.0, y);
+        cr.line_to(width, y);
+    }
+    let _ = cr.stroke();
+
+    // Only the discharging rate is drawn, while charging it's the charger's rate
+    let discharge: Vec<HistoryPoint> = history.rate
+        .iter()
+        .map(|p| match p.state == BatteryState::Discharging as u32 {
+            true => *p,
+            false => HistoryPoint { value: 0.0, ..*p }
+        })
+        .collect();
+    let max_rate = discharge.iter().map(|p| p.value).fold(1.0, f64::max);
+
+    if let (Some(first), Some(last)) = (discharge.first(), discharge.last()) {
+        plot(cr, &discharge, width, height, max_rate);
+        // Closes the area along the bottom
+        cr.line_to(time_x(last.time, width), height);
+        cr.line_to(time_x(first.time, width), height);
+        cr.close_path();
+
+        cr.set_source_rgba(r, g, b, a * 0.3);
+        let _ = cr.fill();
+    }
+
+    if history.charge.len() > 1 {
+        plot(cr, &history.charge, width, height, 100.0);
+        cr.set_line_width(LINE_WIDTH);
+        cr.set_line_join(cairo::LineJoin::Round);
+        cr.set_source_rgba(r, g, b, a);
+        let _ = cr.stroke();
+    }
+}
+
+fn tooltip(history: &BatteryHistory) -> String {
+    let discharging = BatteryState::Discharging as u32;
+    let mut lines = vec!["Last 24 hours: charge and discharge rate".to_string()];
+
+    let screen_on = history.screen_on_time(discharging);
+    if !screen_on.is_zero() {
+        lines.push(format!("Screen-on time on battery: about {}", format_battery_time(screen_on.as_secs() as i64)));
+    }
+
+    // How long it lasts at the day's average discharge rate
+    if let Some(rate) = history.average_discharge_rate(discharging) {
+        if let Some(runtime) = history.runtime_from(100.0, rate) {
+            lines.push(format!("A full charge lasts about {} at {:.1} W", format_battery_time(runtime.as_secs() as i64), rate));
+        }
+        if let Some((percentage, runtime)) = history.last_percentage().and_then(|p| Some((p, history.runtime_from(p, rate)?))) {
+            lines.push(format!("{:.0}% lasts about {}", percentage, format_battery_time(runtime.as_secs() as i64)));
+        }
+    }
+
+    if let Some(accuracy) = history.statistics_accuracy() {
+        lines.push(format!("Discharge statistics: {:.0}% accurate", accuracy));
+    }
+
+    if let Some(cycles) = history.charge_cycles {
+        lines.push(format!("Charge cycles: {cycles}"));
+    }
+
+    lines.join("\n")
+}
+
+/// Reads the main battery's history into the graph. It's hidden if there is no main battery or no history
+async fn refresh(upower: &UPower, area: &DrawingArea, data: &RefCell<Option<BatteryHistory>>) {
+    let (Some(connection), Some(battery)) = (upower.connection(), upower.main_battery()) else {
+        area.set_visible(false);
+        return;
+    };
+
+    match history::read(&connection, &battery.path()).await {
+        Ok(history) => {
+            area.set_tooltip_text(Some(&tooltip(&history)));
+            area.set_visible(history.charge.len() > 1);
+            data.replace(Some(history));
+        },
+        Err(e) => {
+            eprintln!("Failed to read the history of {}: {e}", battery.name());
+            area.set_visible(false);
+            data.replace(None);
+        }
+    }
+
+    area.queue_draw();
+}
+
+/// The main battery's charge and discharge rate over the last 24 hours, refreshed every few minutes
+pub fn build_battery_graph(upower: &UPower) -> DrawingArea {
+    let area = {
+        DrawingArea::builder()
+        .content_height(60)
+        .hexpand(true)
+        .visible(false)
+        .css_classes(["text", "battery_graph"])
+        .build()
+    };
+
+    let data: Rc<RefCell<Option<BatteryHistory>>> = Rc::new(RefCell::new(None));
+
+    area.set_draw_func(glib::clone!(
+        #[strong]
+        data,
+        move |area, cr, width, height| {
+            if let Some(history) = data.borrow().as_ref() {
+                draw_graph(area, cr, width, height, history);
+            }
+        }
+    ));
+
+    // The devices are only there once UPower is reached
+    let (wake_snd, wake_rcv) = async_channel::bounded::<()>(1);
+    upower.connect_init_success_notify(move |_| {
+        let _ = wake_snd.try_send(());
+    });
+
+    // UPower and the graph are only held while refreshing, so the loop stops when the graph is destroyed
+    let upower_ref = upower.downgrade();
+    let area_ref = area.downgrade();
+    glib::spawn_future_local(async move {
+        loop {
+            let (Some(upower), Some(area)) = (upower_ref.upgrade(), area_ref.upgrade()) else {
+                break;
+            };
+            refresh(&upower, &area, &data).await;
+            drop((upower, area));
+
+            future::select(pin!(glib::timeout_future(REFRESH_INTERVAL)), pin!(wake_rcv.recv())).await;
+        }
+    });
+
+    area
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(minute: i64, value: f64, state: BatteryState) -> HistoryPoint {
+        HistoryPoint { time: 1_800_000_000 + minute * 60, value, state: state as u32 }
+    }
+
+    #[test]
+    fn tooltip_without_data() {
+        assert_eq!(tooltip(&BatteryHistory::default()), "Last 24 hours: charge and discharge rate");
+    }
+
+    #[test]
+    fn tooltip_with_history() {
+        let history = BatteryHistory {
+            charge: vec![
+                point(0, 100.0, BatteryState::Discharging),
+                point(30, 90.0, BatteryState::Discharging),
+                point(60, 80.0, BatteryState::Discharging),
+            ],
+            rate: vec![
+                point(0, 9.0, BatteryState::Discharging),
+                point(30, 11.0, BatteryState::Discharging),
+            ],
+            energy_full: 50.0,
+            discharge_profile: vec![(1.0, 60.0); 101],
+            charge_cycles: Some(112)
+        };
+
+        // The gaps are longer than a suspend could be, so only 20 minutes of each count
+        assert_eq!(tooltip(&history), [
+            "Last 24 hours: charge and discharge rate",
+            "Screen-on time on battery: about 40m",
+            "A full charge lasts about 5h 00m at 10.0 W",
+            "80% lasts about 4h 00m",
+            "Discharge statistics: 60% accurate",
+            "Charge cycles: 112",
+        ].join("\n"));
+    }
+}
@@ -0,0 +1,39 @@
+use std::cell::{ OnceCell, RefCell };
+
+use gtk::{ glib, Image, Label, LevelBar };
+use gtk::subclass::prelude::*;
+
+use crate::upower::BatteryDevice;
+
+/// The widgets showing the device, made once when the row is
+pub struct RowWidgets {
+    pub icon: Image,
+    pub name: Label,
+    pub level: LevelBar,
+    pub percentage: Label,
+    pub status: Label
+}
+
+#[derive(Default)]
+pub struct BatteryRow {
+    pub widgets: OnceCell<RowWidgets>,
+    /// The device shown, and what connects it to the widgets until it's unbound
+    pub device: RefCell<Option<BatteryDevice>>,
+    pub bindings: RefCell<Vec<glib::Binding>>,
+    pub handlers: RefCell<Vec<glib::SignalHandlerId>>
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for BatteryRow {
+    const NAME: &'static str = "BatteryRow";
+    type Type = super::BatteryRow;
+    type ParentType = gtk::Box;
+}
+
+impl ObjectImpl for BatteryRow {
+    fn dispose(&self) {
+        self.obj().unbind();
+    }
+}
+impl WidgetImpl for BatteryRow {}
+impl BoxImpl for BatteryRow {}
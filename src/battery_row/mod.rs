@@ -0,0 +1,168 @@
+use glib::Object;
+use gtk::{ Align, Label, glib, prelude::*, subclass::prelude::* };
+use upower_dbus::BatteryState;
+
+use crate::upower::BatteryDevice;
+use crate::ui_elements::format_battery_time;
+
+mod imp;
+use imp::RowWidgets;
+
+// A row of the battery island: the device's icon, its name, its charge and what it's doing.
+// The ListView makes a few of them and reuses them while scrolling, so the widgets are made once,
+// and only the device's properties are bound to them (and unbound) when it's shown in another row.
+
+glib::wrapper!{
+    pub struct BatteryRow(ObjectSubclass<imp::BatteryRow>)
+        @extends gtk::Box, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Orientable;
+}
+
+impl BatteryRow {
+    pub fn new() -> Self {
+        let obj: BatteryRow = Object::builder()
+            .property("orientation", gtk::Orientation::Horizontal)
+            .property("spacing", 10)
+            .build();
+        obj.add_css_class("battery_device");
+
+        let icon = {
+            gtk::Image::builder()
+            .pixel_size(32)
+            .css_classes(["battery_icon"])
+            .build()
+        };
+
+        let name = {
+            Label::builder()
+            .css_classes(["text", "battery_name"])
+            .halign(Align::Start)
+            .ellipsize(gtk::pango::EllipsizeMode::End)
+            .max_width_chars(20)
+            .build()
+        };
+
+        let level = {
+            gtk::LevelBar::builder()
+            .min_value(0.0)
+            .max_value(100.0)
+            .hexpand(true)
+            .valign(Align::Center)
+            .css_classes(["battery_level"])
+            .build()
+        };
+
+        let percentage = {
+            Label::builder()
+            .css_classes(["text", "battery_percentage"])
+            .build()
+        };
+
+        let charge = gtk::Box::builder().orientation(gtk::Orientation::Horizontal).spacing(8).build();
+        charge.append(&level);
+        charge.append(&percentage);
+
+        let status = {
+            Label::builder()
+            .css_classes(["text", "battery_status"])
+            .halign(Align::Start)
+            .build()
+        };
+
+        let details = gtk::Box::builder().orientation(gtk::Orientation::Vertical).hexpand(true).build();
+        details.append(&name);
+        details.append(&charge);
+        details.append(&status);
+
+        obj.append(&icon);
+        obj.append(&details);
+
+        let _ = obj.imp().widgets.set(RowWidgets { icon, name, level, percentage, status });
+
+        obj
+    }
+
+    fn widgets(&self) -> &RowWidgets {
+        self.imp().widgets.get().expect("The widgets are made in new")
+    }
+
+    /// Shows a device in the row, until it's unbound
+    pub fn bind(&self, device: &BatteryDevice) {
+        self.unbind();
+        let widgets = self.widgets();
+
+        let bindings = vec![
+            device.bind_property("name", &widgets.name, "label").sync_create().build(),
+            device.bind_property("percentage", &widgets.level, "value").sync_create().build(),
+            device.bind_property("percentage", &widgets.percentage, "label")
+                .transform_to(|_, percentage: f64| Some(format!("{:.0}%", percentage)))
+                .sync_create()
+                .build(),
+        ];
+
+        // The icon and the status depend on several properties, so they are updated on any of them changing
+        self.update(device);
+        let handlers = ["icon-name", "percentage", "state", "time-to-empty", "time-to-full", "energy-rate"]
+            .into_iter()
+            .map(|property| device.connect_notify_local(Some(property), glib::clone!(
+                #[weak(rename_to = row)]
+                self,
+                move |device, _| row.update(device)
+            )))
+            .collect();
+
+        self.imp().bindings.replace(bindings);
+        self.imp().handlers.replace(handlers);
+        self.imp().device.replace(Some(device.clone()));
+    }
+
+    /// Disconnects the row from the device it shows
+    pub fn unbind(&self) {
+        for binding in self.imp().bindings.take() {
+            binding.unbind();
+        }
+
+        if let Some(device) = self.imp().device.take() {
+            for handler in self.imp().handlers.take() {
+                device.disconnect(handler);
+            }
+        }
+    }
+
+    fn update(&self, device: &BatteryDevice) {
+        let widgets = self.widgets();
+        widgets.icon.set_icon_name(Some(&device.status_icon()));
+        match battery_status(device) {
+            Some(text) => {
+                widgets.status.set_label(&text);
+                widgets.status.set_visible(true);
+            },
+            None => widgets.status.set_visible(false)
+        }
+    }
+}
+
+/// What the battery is doing, like "⚡ 1h 20m until full" or "3h 05m left", with the power going in or out.
+/// None if UPower doesnt know its state
+fn battery_status(device: &BatteryDevice) -> Option<String> {
+    let status = match device.state() {
+        s if s == BatteryState::Charging as u32 => match device.time_to_full() {
+            0 => "⚡ Charging".to_string(),
+            seconds => format!("⚡ {} until full", format_battery_time(seconds))
+        },
+        s if s == BatteryState::Discharging as u32 => match device.time_to_empty() {
+            0 => "Discharging".to_string(),
+            seconds => format!("{} left", format_battery_time(seconds))
+        },
+        s if s == BatteryState::FullyCharged as u32 => "Fully charged".to_string(),
+        s if s == BatteryState::PendingCharge as u32 => "Plugged in, not charging".to_string(),
+        s if s == BatteryState::PendingDischarge as u32 => "Waiting to discharge".to_string(),
+        s if s == BatteryState::Empty as u32 => "Empty".to_string(),
+        _ => return None
+    };
+
+    match device.energy_rate() {
+        rate if rate > 0.0 => Some(format!("{status} · {:.1} W", rate)),
+        _ => Some(status)
+    }
+}
@@ -0,0 +1,312 @@
+use gtk::glib::{ self, KeyFile, KeyFileFlags };
+use chrono::{ Duration, Utc };
+use xml::reader::{ EventReader, XmlEvent };
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{ Path, PathBuf };
+
+use crate::config::CalDavConfig;
+
+// Events from a CalDAV server are mirrored to .ics files in the cache ($XDG_CACHE_HOME/dinfo/caldav/<name>),
+// so they are loaded and watched like any other local calendar.
+//
+// A sync first asks for the etag of every event in the time range (REPORT calendar-query),
+// then only downloads the ones whose etag changed (REPORT calendar-multiget), and removes the ones that are gone.
+// The etags are kept in index.ini next to the files, so nothing is downloaded again after a restart.
+
+/// Only the events around now are synced, the agenda doesnt show anything further
+const SYNC_PAST: Duration = Duration::days(7);
+const SYNC_FUTURE: Duration = Duration::days(60);
+/// How many events are downloaded in a single multiget request
+const MULTIGET_BATCH: usize = 50;
+
+const INDEX_FILE: &str = "index.ini";
+
+/// A calendar resource on the server
+#[derive(Debug, Default)]
+struct Resource {
+    href: String,
+    etag: String,
+    /// Only set in multiget responses
+    calendar_data: Option<String>
+}
+
+/// The cache directory of a CalDAV calendar
+pub fn cache_dir(name: &str) -> PathBuf {
+    glib::user_cache_dir().join("dinfo").join("caldav").join(file_name(name))
+}
+
+/// Replaces the characters that dont belong in a file name
+fn file_name(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') { c } else { '_' })
+        .collect()
+}
+
+/// The cached file of a resource, named after the last part of its href
+fn resource_file(href: &str) -> String {
+    let name = file_name(href.trim_end_matches('/').rsplit('/').next().unwrap_or(href));
+    if name.ends_with(".ics") { name } else { format!("{name}.ics") }
+}
+
+/// Reads the etags of the cached resources, by href
+async fn read_index(dir: &Path) -> HashMap<String, String> {
+    let Ok(data) = tokio::fs::read_to_string(dir.join(INDEX_FILE)).await else {
+        return HashMap::new();
+    };
+
+    let file = KeyFile::new();
+    if file.load_from_data(&data, KeyFileFlags::NONE).is_err() {
+        return HashMap::new();
+    }
+
+    file.groups()
+        .iter()
+        .filter_map(|group| {
+            let href = file.string(group.to_gstr().as_str(), "href").ok()?;
+            let etag = file.string(group.to_gstr().as_str(), "etag").ok()?;
+            Some((href.to_string(), etag.to_string()))
+        })
+        .collect()
+}
+
+async fn write_index(dir: &Path, index: &HashMap<String, String>) -> Result<(), Box<dyn Error>> {
+    // The KeyFile cant be kept across the await, it isnt Send
+    let data = {
+        let file = KeyFile::new();
+        for (href, etag) in index {
+            let group = resource_file(href);
+            file.set_string(&group, "href", href);
+            file.set_string(&group, "etag", etag);
+        }
+        file.to_data().to_string()
+    };
+
+    tokio::fs::write(dir.join(INDEX_FILE), data).await?;
+    Ok(())
+}
+
+/// Reads a multistatus response, every response with an etag is returned
+fn parse_multistatus(body: &str) -> Result<Vec<Resource>, Box<dyn Error>> {
+    let mut resources = Vec::new();
+    let mut current: Option<Resource> = None;
+    // The element whose text is being read
+    let mut reading: Option<String> = None;
+    let mut text = String::new();
+
+    for event in EventReader::from_str(body) {
+        match event? {
+            XmlEvent::StartElement { name, .. } => match name.local_name.as_str() {
+                "response" => current = Some(Resource::default()),
+                "href" | "getetag" | "calendar-data" => {
+                    reading = Some(name.local_name);
+                    text.clear();
+                },
+                _ => {}
+            },
+            XmlEvent::Characters(chars) | XmlEvent::CData(chars) if reading.is_some() => text.push_str(&chars),
+            XmlEvent::EndElement { name } => {
+                let Some(resource) = &mut current else {
+                    continue;
+                };
+
+                match name.local_name.as_str() {
+                    "href" if reading.is_some() && resource.href.is_empty() => resource.href = text.trim().to_string(),
+                    "getetag" => resource.etag = text.trim().to_string(),
+                    "calendar-data" => resource.calendar_data = Some(text.clone()),
+                    "response" => {
+                        // The collection itself, and events that failed (404) dont have an etag
+                        if let Some(resource) = current.take().filter(|r| !r.etag.is_empty()) {
+                            resources.push(resource);
+                        }
+                    },
+                    _ => {}
+                }
+                reading = None;
+            },
+            _ => {}
+        }
+    }
+
+    Ok(resources)
+}
+
+/// The password from the config, or the output of the password command
+async fn password(config: &CalDavConfig) -> Result<Option<String>, Box<dyn Error>> {
+    if let Some(command) = &config.password_command {
+        let output = tokio::process::Command::new("sh").arg("-c").arg(command).output().await?;
+        if !output.status.success() {
+            return Err(format!("password command exited with {}", output.status).into());
+        }
+        return Ok(Some(String::from_utf8(output.stdout)?.trim_end_matches('\n').to_string()));
+    }
+
+    Ok(config.password.clone())
+}
+
+/// Sends a REPORT request to the calendar, and returns the responses in it
+async fn report(client: &reqwest::Client, config: &CalDavConfig, password: Option<&str>, body: String) -> Result<Vec<Resource>, Box<dyn Error>> {
+    let mut request = client
+        .request(reqwest::Method::from_bytes(b"REPORT")?, &config.url)
+        .header("Depth", "1")
+        .header("Content-Type", "application/xml; charset=utf-8")
+        .body(body);
+
+    if let Some(username) = &config.username {
+        request = request.basic_auth(username, password);
+    }
+
+    let response = request.send().await?.error_for_status()?;
+    parse_multistatus(&response.text().await?)
+}
+
+/// Escapes the characters that are special in xml text
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Syncs a calendar to its cache directory, returns how many events were downloaded and removed
+pub async fn sync(client: &reqwest::Client, config: &CalDavConfig) -> Result<(usize, usize), Box<dyn Error>> {
+    // This runs on a tokio worker, so the files and the password command are waited on asynchronously
+    let dir = cache_dir(&config.name);
+    tokio::fs::create_dir_all(&dir).await?;
+
+    let password = password(config).await?;
+    let now = Utc::now();
+
+    let query = format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:prop><D:getetag/></D:prop>
+  <C:filter>
+    <C:comp-filter name="VCALENDAR">
+      <C:comp-filter name="VEVENT">
+        <C:time-range start="{}" end="{}"/>
+      </C:comp-filter>
+    </C:comp-filter>
+  </C:filter>
+</C:calendar-query>"#,
+        (now - SYNC_PAST).format("%Y%m%dT%H%M%SZ"),
+        (now + SYNC_FUTURE).format("%Y%m%dT%H%M%SZ")
+    );
+
+    let remote: HashMap<String, String> = report(client, config, password.as_deref(), query)
+        .await?
+        .into_iter()
+        .map(|r| (r.href, r.etag))
+        .collect();
+
+    let mut index = read_index(&dir).await;
+
+    // Events that are gone from the server (or from the range)
+    let removed: Vec<String> = index.keys().filter(|href| !remote.contains_key(*href)).cloned().collect();
+    for href in &removed {
+        index.remove(href);
+        if let Err(e) = tokio::fs::remove_file(dir.join(resource_file(href))).await {
+            eprintln!("Failed to remove cached event {href}: {e}");
+        }
+    }
+
+    let mut changed: Vec<&String> = Vec::new();
+    for (href, etag) in &remote {
+        let cached = tokio::fs::try_exists(dir.join(resource_file(href))).await.unwrap_or(false);
+        if index.get(href) != Some(etag) || !cached {
+            changed.push(href);
+        }
+    }
+
+    let mut downloaded = 0;
+    for batch in changed.chunks(MULTIGET_BATCH) {
+        let hrefs: String = batch.iter().map(|href| format!("  <D:href>{}</D:href>\n", escape_xml(href))).collect();
+        let multiget = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<C:calendar-multiget xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:prop><D:getetag/><C:calendar-data/></D:prop>
+{hrefs}</C:calendar-multiget>"#
+        );
+
+        let resources = report(client, config, password.as_deref(), multiget).await?;
+        for resource in resources {
+            let Some(data) = resource.calendar_data else {
+                continue;
+            };
+
+            tokio::fs::write(dir.join(resource_file(&resource.href)), data).await?;
+            index.insert(resource.href, resource.etag);
+            downloaded += 1;
+        }
+    }
+
+    if downloaded > 0 || !removed.is_empty() {
+        write_index(&dir, &index).await?;
+    }
+
+    Ok((downloaded, removed.len()))
+}
+
+/// Syncs the calendars now, and then on their interval. Runs on the tokio runtime
+pub fn start(calendars: Vec<CalDavConfig>) {
+    let client = reqwest::Client::new();
+
+    for config in calendars {
+        let client = client.clone();
+
+        crate::ui_elements::runtime().spawn(async move {
+            loop {
+                match sync(&client, &config).await {
+                    Ok((0, 0)) => {},
+                    Ok((downloaded, removed)) => println!("Synced calendar {}: {downloaded} updated, {removed} removed", config.name),
+                    Err(e) => eprintln!("Failed to sync calendar {}: {e}", config.name)
+                }
+
+                tokio::time::sleep(config.interval).await;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Responses in the format Radicale 3 sends them, to the calendar-query and calendar-multiget requests of sync()
+    const QUERY_RESPONSE: &str = include_str!("fixtures/radicale_query.xml");
+    const MULTIGET_RESPONSE: &str = include_str!("fixtures/radicale_multiget.xml");
+
+    #[test]
+    fn parses_query_response() {
+        let resources = parse_multistatus(QUERY_RESPONSE).unwrap();
+
+        // The collection itself has no etag
+        let hrefs: Vec<&str> = resources.iter().map(|r| r.href.as_str()).collect();
+        assert_eq!(hrefs, vec!["/user/team-calendar/5f3a1c2e-standup.ics", "/user/team-calendar/review%20%26%20retro.ics"]);
+        assert_eq!(resources[0].etag, "\"a1b2c3d4e5f60718293a4b5c6d7e8f90a1b2c3d4e5f60718293a4b5c6d7e8f9\"");
+        assert!(resources.iter().all(|r| r.calendar_data.is_none()));
+    }
+
+    #[test]
+    fn parses_multiget_response() {
+        let resources = parse_multistatus(MULTIGET_RESPONSE).unwrap();
+
+        // The event that was deleted in the meantime comes back as a 404 without an etag
+        assert_eq!(resources.len(), 1);
+
+        let data = resources[0].calendar_data.as_deref().unwrap();
+        assert!(data.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(data.contains("SUMMARY:Standup & planning\r\n"));
+        assert!(data.trim_end().ends_with("END:VCALENDAR"));
+    }
+
+    #[test]
+    fn rejects_broken_xml() {
+        assert!(parse_multistatus("<multistatus xmlns=\"DAV:\"><response>").is_err());
+    }
+
+    #[test]
+    fn names_cached_files_after_hrefs() {
+        assert_eq!(resource_file("/user/team-calendar/5f3a1c2e-standup.ics"), "5f3a1c2e-standup.ics");
+        assert_eq!(resource_file("/user/team-calendar/review%20%26%20retro.ics"), "review_20_26_20retro.ics");
+        assert_eq!(resource_file("/calendars/user/event-1/"), "event-1.ics");
+    }
+}
@@ -0,0 +1,363 @@
+use chrono::{ Duration, NaiveDate, NaiveDateTime };
+use chrono_tz::Tz;
+
+use super::{ Event, EventStart, Zone };
+use super::rrule::RRule;
+
+// A small iCalendar (RFC 5545) reader, only the VEVENT parts needed for the agenda.
+// Lines are unfolded first, then every property is split to a name, parameters and a value.
+
+/// A content line, like DTSTART;TZID=Europe/Budapest:20250101T090000
+#[derive(Debug)]
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String
+}
+
+impl Property {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+}
+
+/// Joins the folded lines (the ones starting with a space or tab continue the previous one)
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string())
+        }
+    }
+
+    lines
+}
+
+fn parse_property(line: &str) -> Option<Property> {
+    // The value starts after the first colon that isnt in a quoted parameter
+    let mut in_quotes = false;
+    let colon = line.char_indices().find(|&(_, c)| {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        }
+        c == ':' && !in_quotes
+    })?.0;
+
+    let mut parts = line[..colon].split(';');
+    let name = parts.next()?.to_ascii_uppercase();
+    let params = parts
+        .filter_map(|p| p.split_once('='))
+        .map(|(n, v)| (n.to_ascii_uppercase(), v.trim_matches('"').to_string()))
+        .collect();
+
+    Some(Property { name, params, value: line[colon + 1..].to_string() })
+}
+
+/// Removes the escaping of a TEXT value
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n' | 'N') => result.push('\n'),
+            Some(other) => result.push(other),
+            None => {}
+        }
+    }
+
+    result
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.get(..8)?, "%Y%m%d").ok()
+}
+
+fn parse_date_time(value: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value.trim_end_matches('Z'), "%Y%m%dT%H%M%S").ok()
+}
+
+/// The zone of a DATE-TIME property: UTC if it ends with Z, the TZID if it's a known IANA zone, floating otherwise
+fn zone_of(property: &Property) -> Zone {
+    if property.value.ends_with('Z') {
+        return Zone::Utc;
+    }
+
+    match property.param("TZID") {
+        // Some apps prefix the zone, like /mozilla.org/20050126_1/Europe/Budapest
+        Some(tzid) => tzid
+            .parse::<Tz>()
+            .ok()
+            .or_else(|| tzid.split('/').skip_while(|p| !p.starts_with(char::is_uppercase)).collect::<Vec<_>>().join("/").parse().ok())
+            .map(Zone::Named)
+            .unwrap_or(Zone::Floating),
+        None => Zone::Floating
+    }
+}
+
+/// Parses a DTSTART, DTEND, RECURRENCE-ID or EXDATE value (only the first one of a list)
+fn parse_start(property: &Property) -> Option<EventStart> {
+    let value = property.value.split(',').next()?;
+
+    if property.param("VALUE") == Some("DATE") || value.len() == 8 {
+        return parse_date(value).map(EventStart::Date);
+    }
+
+    Some(EventStart::Time { time: parse_date_time(value)?, zone: zone_of(property) })
+}
+
+/// Parses every value of an EXDATE (it can be a comma separated list)
+fn parse_exdates(property: &Property) -> Vec<EventStart> {
+    let zone = zone_of(property);
+
+    property.value
+        .split(',')
+        .filter_map(|value| match value.len() {
+            8 => parse_date(value).map(EventStart::Date),
+            _ => parse_date_time(value).map(|time| EventStart::Time { time, zone: zone.clone() })
+        })
+        .collect()
+}
+
+/// Parses a DURATION value, like PT1H30M, P1D or -PT15M
+fn parse_duration(value: &str) -> Option<Duration> {
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.trim_start_matches('+'))
+    };
+    let value = value.strip_prefix('P')?;
+
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    for c in value.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => {},
+            unit => {
+                let n: i64 = number.parse().ok()?;
+                number.clear();
+                total += match unit {
+                    'W' => Duration::weeks(n),
+                    'D' => Duration::days(n),
+                    'H' => Duration::hours(n),
+                    'M' => Duration::minutes(n),
+                    'S' => Duration::seconds(n),
+                    _ => return None
+                };
+            }
+        }
+    }
+
+    Some(if negative { -total } else { total })
+}
+
+/// The wall time of an UNTIL value in the zone of the event
+fn until_in_zone(value: &str, zone: &Zone) -> Option<NaiveDateTime> {
+    if value.len() == 8 {
+        // Date only, the whole day is included
+        return parse_date(value)?.and_hms_opt(23, 59, 59);
+    }
+
+    let time = parse_date_time(value)?;
+    if !value.ends_with('Z') {
+        return Some(time);
+    }
+
+    match zone {
+        Zone::Utc => Some(time),
+        Zone::Named(tz) => Some(time.and_utc().with_timezone(tz).naive_local()),
+        Zone::Floating => Some(time.and_utc().with_timezone(&chrono::Local).naive_local())
+    }
+}
+
+/// Every VEVENT of an iCalendar file. Events without a DTSTART, or with a recurrence that isnt supported are skipped
+pub fn parse(text: &str) -> Vec<Event> {
+    let mut events = Vec::new();
+    let mut properties: Option<Vec<Property>> = None;
+    // Alarms and other components can be nested in the event, their properties are ignored
+    let mut nested = 0;
+
+    for line in unfold(text) {
+        let Some(property) = parse_property(&line) else {
+            continue;
+        };
+
+        match (property.name.as_str(), property.value.to_ascii_uppercase().as_str()) {
+            ("BEGIN", "VEVENT") => properties = Some(Vec::new()),
+            ("END", "VEVENT") => {
+                if let Some(event) = properties.take().and_then(|p| event_from(&p)) {
+                    events.push(event);
+                }
+            },
+            ("BEGIN", _) if properties.is_some() => nested += 1,
+            ("END", _) if properties.is_some() => nested -= 1,
+            _ if nested > 0 => {},
+            _ => {
+                if let Some(properties) = &mut properties {
+                    properties.push(property);
+                }
+            }
+        }
+    }
+
+    events
+}
+
+fn event_from(properties: &[Property]) -> Option<Event> {
+    let get = |name: &str| properties.iter().find(|p| p.name == name);
+
+    let start_property = get("DTSTART")?;
+    let start = parse_start(start_property)?;
+
+    let duration = match (get("DTEND").and_then(parse_start), get("DURATION").and_then(|p| parse_duration(&p.value))) {
+        // The end can be in another zone than the start
+        (Some(end), _) => match (start.is_all_day(), start.to_local(), end.to_local()) {
+            (false, Some(start), Some(end)) => end - start,
+            _ => end.naive() - start.naive()
+        },
+        (None, Some(duration)) => duration,
+        // Without an end, all day events last the day, and the others take no time
+        (None, None) => match start {
+            EventStart::Date(_) => Duration::days(1),
+            EventStart::Time { .. } => Duration::zero()
+        }
+    };
+
+    // Showing only the first instance of a rule that cant be expanded would be wrong, the event is skipped instead
+    let zone = start.zone();
+    let rrule = match get("RRULE") {
+        Some(property) => match RRule::parse(&property.value, |until| until_in_zone(until, &zone)) {
+            Ok(rule) => Some(rule),
+            Err(e) => {
+                let summary = get("SUMMARY").map(|p| unescape(&p.value)).unwrap_or_default();
+                eprintln!("Skipping event '{summary}', its RRULE {} cant be expanded: {e}", property.value);
+                return None;
+            }
+        },
+        None => None
+    };
+
+    Some(Event {
+        uid: get("UID").map(|p| p.value.clone()).unwrap_or_default(),
+        summary: get("SUMMARY").map(|p| unescape(&p.value)).unwrap_or_default(),
+        location: get("LOCATION").map(|p| unescape(&p.value)).filter(|l| !l.is_empty()),
+        start,
+        duration,
+        rrule,
+        exdates: properties.iter().filter(|p| p.name == "EXDATE").flat_map(parse_exdates).collect(),
+        recurrence_id: get("RECURRENCE-ID").and_then(parse_start),
+        cancelled: get("STATUS").is_some_and(|p| p.value.eq_ignore_ascii_case("CANCELLED"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::occurrences_between;
+
+    use chrono::{ Local, NaiveDate, TimeZone };
+
+    const FOLDED: &str = include_str!("fixtures/folded.ics");
+    const RECURRING: &str = include_str!("fixtures/recurring.ics");
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn local(y: i32, m: u32, d: u32, h: u32, min: u32) -> chrono::DateTime<Local> {
+        Local.from_local_datetime(&date(y, m, d).and_hms_opt(h, min, 0).unwrap()).earliest().unwrap()
+    }
+
+    #[test]
+    fn unfolds_lines() {
+        let lines = unfold("SUMMARY:Long\r\n  line\r\n\t continued\r\nUID:1\r\n");
+        assert_eq!(lines, vec!["SUMMARY:Long line continued", "UID:1"]);
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("PT1H30M"), Some(Duration::minutes(90)));
+        assert_eq!(parse_duration("P1D"), Some(Duration::days(1)));
+        assert_eq!(parse_duration("P2W"), Some(Duration::weeks(2)));
+        assert_eq!(parse_duration("P1DT2H"), Some(Duration::hours(26)));
+        assert_eq!(parse_duration("-PT15M"), Some(Duration::minutes(-15)));
+        assert_eq!(parse_duration("+PT10S"), Some(Duration::seconds(10)));
+        assert_eq!(parse_duration("1H"), None);
+        assert_eq!(parse_duration("PT5X"), None);
+        assert_eq!(parse_duration("PTH"), None);
+    }
+
+    #[test]
+    fn parses_folded_event() {
+        let events = parse(FOLDED);
+        assert_eq!(events.len(), 2);
+
+        let review = &events[0];
+        assert_eq!(review.summary, "Design review, part 2 with a summary that is long enough to be folded by the calendar app");
+        assert_eq!(review.location.as_deref(), Some("Room 4;second floor"));
+        assert_eq!(review.start, EventStart::Time {
+            time: date(2026, 3, 10).and_hms_opt(14, 0, 0).unwrap(),
+            zone: Zone::Named(chrono_tz::Europe::Budapest)
+        });
+        // The alarm's DURATION isnt the event's
+        assert_eq!(review.duration, Duration::minutes(90));
+        assert!(review.rrule.is_none());
+
+        let offsite = &events[1];
+        assert_eq!(offsite.start, EventStart::Date(date(2026, 3, 12)));
+        assert_eq!(offsite.duration, Duration::days(2));
+        assert!(offsite.cancelled);
+    }
+
+    #[test]
+    fn skips_events_with_unsupported_rules() {
+        let events = parse(RECURRING);
+        assert_eq!(events.len(), 3);
+        assert!(events.iter().all(|e| e.summary != "Drink water"));
+    }
+
+    #[test]
+    fn expands_weekly_rule_with_exdate_and_override() {
+        let events = parse(RECURRING);
+        let occurrences = occurrences_between(&events, local(2026, 1, 1, 0, 0), local(2026, 1, 31, 0, 0));
+
+        let standups: Vec<(String, chrono::DateTime<Local>)> = occurrences
+            .iter()
+            .filter(|o| o.summary.starts_with("Standup"))
+            .map(|o| (o.summary.clone(), o.start))
+            .collect();
+
+        // The 7th is an EXDATE, the 12th is moved, the rule ends on the 16th
+        assert_eq!(standups, vec![
+            ("Standup".to_string(), local(2026, 1, 5, 9, 30)),
+            ("Standup".to_string(), local(2026, 1, 9, 9, 30)),
+            ("Standup (moved)".to_string(), local(2026, 1, 12, 11, 0)),
+            ("Standup".to_string(), local(2026, 1, 14, 9, 30)),
+            ("Standup".to_string(), local(2026, 1, 16, 9, 30)),
+        ]);
+        assert_eq!(occurrences.iter().find(|o| o.summary == "Standup").unwrap().end, local(2026, 1, 5, 9, 45));
+    }
+
+    #[test]
+    fn expands_last_weekday_of_the_month() {
+        let events = parse(RECURRING);
+        let occurrences = occurrences_between(&events, local(2026, 1, 1, 0, 0), local(2026, 12, 31, 0, 0));
+
+        let invoices: Vec<NaiveDate> = occurrences
+            .iter()
+            .filter(|o| o.summary == "Send the invoices")
+            .map(|o| o.start.date_naive())
+            .collect();
+
+        // COUNT=3, the last weekday of January, February and March
+        assert_eq!(invoices, vec![date(2026, 1, 30), date(2026, 2, 27), date(2026, 3, 31)]);
+        assert!(occurrences.iter().filter(|o| o.summary == "Send the invoices").all(|o| o.all_day));
+    }
+}
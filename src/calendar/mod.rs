@@ -0,0 +1,292 @@
+use gtk::{ gio, glib, prelude::* };
+use chrono::{ DateTime, Duration, Local, NaiveDateTime, TimeZone, Utc };
+use chrono_tz::Tz;
+
+use std::cell::{ Cell, RefCell };
+use std::collections::HashSet;
+use std::path::{ Path, PathBuf };
+use std::rc::Rc;
+
+use crate::agenda_state::AgendaState;
+
+mod ics;
+mod rrule;
+pub mod caldav;
+use rrule::RRule;
+
+// Events are read from .ics files, or from directories of them (like vdirsyncer's collections).
+// Recurring events are kept as they are in the file, and only expanded for the range that is shown.
+
+/// Wait this long after a change before reloading, a sync usually touches many files at once
+const RELOAD_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+
+thread_local! {
+    // Need to be kept alive, the monitors stop when they are dropped
+    static MONITORS: RefCell<Vec<gio::FileMonitor>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The time zone of an event's times
+#[derive(Debug, Clone, PartialEq)]
+pub enum Zone {
+    Utc,
+    Named(Tz),
+    /// No zone, the time is the same everywhere (the local one)
+    Floating
+}
+
+/// The start of an event, a whole day or a time in a zone
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventStart {
+    Date(chrono::NaiveDate),
+    Time { time: NaiveDateTime, zone: Zone }
+}
+
+impl EventStart {
+    /// The wall time, midnight for whole days
+    pub fn naive(&self) -> NaiveDateTime {
+        match self {
+            Self::Date(date) => date.and_time(chrono::NaiveTime::MIN),
+            Self::Time { time, .. } => *time
+        }
+    }
+
+    pub fn zone(&self) -> Zone {
+        match self {
+            Self::Date(_) => Zone::Floating,
+            Self::Time { zone, .. } => zone.clone()
+        }
+    }
+
+    /// The same kind of start, at another wall time (used for the instances of recurring events)
+    fn with_naive(&self, time: NaiveDateTime) -> Self {
+        match self {
+            Self::Date(_) => Self::Date(time.date()),
+            Self::Time { zone, .. } => Self::Time { time, zone: zone.clone() }
+        }
+    }
+
+    /// Converts to local time. Times skipped by a DST change are moved forward an hour
+    pub fn to_local(&self) -> Option<DateTime<Local>> {
+        fn resolve<T: TimeZone>(zone: &T, time: NaiveDateTime) -> Option<DateTime<Local>> {
+            zone.from_local_datetime(&time)
+                .earliest()
+                .or_else(|| zone.from_local_datetime(&(time + Duration::hours(1))).earliest())
+                .map(|t| t.with_timezone(&Local))
+        }
+
+        match self.zone() {
+            Zone::Utc => Some(Utc.from_utc_datetime(&self.naive()).with_timezone(&Local)),
+            Zone::Named(tz) => resolve(&tz, self.naive()),
+            Zone::Floating => resolve(&Local, self.naive())
+        }
+    }
+
+    pub fn is_all_day(&self) -> bool {
+        matches!(self, Self::Date(_))
+    }
+}
+
+/// A VEVENT, recurring ones are not expanded
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub uid: String,
+    pub summary: String,
+    pub location: Option<String>,
+    pub start: EventStart,
+    pub duration: Duration,
+    pub rrule: Option<RRule>,
+    /// Instances of the recurrence that are removed
+    pub exdates: Vec<EventStart>,
+    /// Set if this event replaces a single instance of a recurring event with the same uid
+    pub recurrence_id: Option<EventStart>,
+    pub cancelled: bool
+}
+
+/// A single instance of an event, in local time
+#[derive(Debug, Clone)]
+pub struct Occurrence {
+    pub summary: String,
+    pub location: Option<String>,
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+    pub all_day: bool
+}
+
+impl Occurrence {
+    fn new(event: &Event, start: &EventStart) -> Option<Self> {
+        let local_start = start.to_local()?;
+        let end = start.with_naive(start.naive() + event.duration).to_local()?;
+
+        Some(Occurrence {
+            summary: event.summary.clone(),
+            location: event.location.clone(),
+            start: local_start,
+            end: end.max(local_start),
+            all_day: start.is_all_day()
+        })
+    }
+
+    /// True if any part of it is between `from` and `to`. Events without a length only count if they start in it
+    fn overlaps(&self, from: DateTime<Local>, to: DateTime<Local>) -> bool {
+        self.start < to && (self.end > from || self.start >= from)
+    }
+}
+
+/// Expands the events to the occurrences that overlap the range, sorted by their start
+pub fn occurrences_between(events: &[Event], from: DateTime<Local>, to: DateTime<Local>) -> Vec<Occurrence> {
+    // Instances of recurring events that are replaced by a modified copy
+    let overridden: HashSet<(&str, Option<DateTime<Local>>)> = events
+        .iter()
+        .filter_map(|e| Some((e.uid.as_str(), e.recurrence_id.as_ref()?.to_local())))
+        .collect();
+
+    let mut occurrences = Vec::new();
+
+    for event in events.iter().filter(|e| !e.cancelled) {
+        let Some(rule) = event.rrule.as_ref().filter(|_| event.recurrence_id.is_none()) else {
+            occurrences.extend(Occurrence::new(event, &event.start).filter(|o| o.overlaps(from, to)));
+            continue;
+        };
+
+        let exdates: HashSet<Option<DateTime<Local>>> = event.exdates.iter().map(|e| e.to_local()).collect();
+
+        for instance in rule.instances(event.start.naive()) {
+            let start = event.start.with_naive(instance);
+            let Some(occurrence) = Occurrence::new(event, &start) else {
+                continue;
+            };
+
+            if occurrence.start >= to {
+                break;
+            }
+
+            let local_start = Some(occurrence.start);
+            if exdates.contains(&local_start) || overridden.contains(&(event.uid.as_str(), local_start)) {
+                continue;
+            }
+
+            if occurrence.overlaps(from, to) {
+                occurrences.push(occurrence);
+            }
+        }
+    }
+
+    occurrences.sort_by_key(|o| (!o.all_day, o.start));
+    occurrences
+}
+
+/// Reads the events of a .ics file, or of every .ics file in a directory (and its subdirectories)
+fn load_path(path: &Path, events: &mut Vec<Event>) {
+    if path.is_dir() {
+        let entries = match std::fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!("Failed to read calendar directory {}: {e}", path.display());
+                return;
+            }
+        };
+
+        for entry in entries.flatten() {
+            let entry_path = entry.path();
+            if entry_path.is_dir() || entry_path.extension().is_some_and(|e| e.eq_ignore_ascii_case("ics")) {
+                load_path(&entry_path, events);
+            }
+        }
+        return;
+    }
+
+    match std::fs::read_to_string(path) {
+        Ok(text) => events.extend(ics::parse(&text)),
+        Err(e) => eprintln!("Failed to read calendar {}: {e}", path.display())
+    }
+}
+
+/// Reads every event from the configured paths
+pub fn load(paths: &[PathBuf]) -> Vec<Event> {
+    let mut events = Vec::new();
+    for path in paths {
+        load_path(path, &mut events);
+    }
+    events
+}
+
+/// Reads the calendars on another thread, and puts the events in the state
+async fn reload(state: AgendaState, paths: Vec<PathBuf>) {
+    state.set_is_loading(true);
+
+    match gio::spawn_blocking(move || load(&paths)).await {
+        Ok(events) => state.set_events(events),
+        Err(_) => eprintln!("Failed to load the calendars")
+    }
+
+    state.set_is_loading(false);
+}
+
+/// The paths and the directories in them, every one of them needs its own monitor
+fn watched_paths(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut watched = Vec::new();
+    let mut stack = paths.to_vec();
+
+    while let Some(path) = stack.pop() {
+        if let Ok(entries) = std::fs::read_dir(&path) {
+            stack.extend(entries.flatten().map(|e| e.path()).filter(|p| p.is_dir()));
+        }
+        watched.push(path);
+    }
+
+    watched
+}
+
+/// Loads the calendars into the state, and reloads them every time one of the files changes
+pub fn load_and_watch(state: &AgendaState, paths: Vec<PathBuf>) {
+    glib::spawn_future_local(reload(state.clone(), paths.clone()));
+
+    let reload_pending = Rc::new(Cell::new(false));
+
+    for path in watched_paths(&paths) {
+        let monitor = match gio::File::for_path(&path).monitor(gio::FileMonitorFlags::WATCH_MOVES, gio::Cancellable::NONE) {
+            Ok(m) => m,
+            Err(e) => {
+                eprintln!("Failed to watch calendar {} for changes: {e}", path.display());
+                continue;
+            }
+        };
+
+        monitor.connect_changed(glib::clone!(
+            #[weak]
+            state,
+            #[strong]
+            paths,
+            #[strong]
+            reload_pending,
+            move |_, _, _, event| {
+                let is_change = matches!(
+                    event,
+                    gio::FileMonitorEvent::ChangesDoneHint |
+                    gio::FileMonitorEvent::Created |
+                    gio::FileMonitorEvent::Deleted |
+                    gio::FileMonitorEvent::MovedIn |
+                    gio::FileMonitorEvent::MovedOut |
+                    gio::FileMonitorEvent::Renamed
+                );
+                if !is_change || reload_pending.get() {
+                    return;
+                }
+
+                reload_pending.set(true);
+                glib::timeout_add_local_once(RELOAD_DELAY, glib::clone!(
+                    #[strong]
+                    paths,
+                    #[strong]
+                    reload_pending,
+                    move || {
+                        reload_pending.set(false);
+                        glib::spawn_future_local(reload(state, paths));
+                    }
+                ));
+            }
+        ));
+
+        MONITORS.with(|m| m.borrow_mut().push(monitor));
+    }
+}
@@ -0,0 +1,370 @@
+use chrono::{ Datelike, Duration, Months, NaiveDate, NaiveDateTime, Weekday };
+
+use std::error::Error;
+
+// RFC 5545 recurrence rules, the parts calendar apps actually write:
+// FREQ (daily to yearly), INTERVAL, COUNT, UNTIL, BYDAY, BYMONTHDAY, BYMONTH, BYSETPOS and WKST.
+// Everything is expanded in the event's own wall time, so a 9:00 meeting stays at 9:00 across DST changes.
+
+/// Stop looking for instances after this many periods without finding one (like BYMONTHDAY=30 with BYMONTH=2)
+const MAX_EMPTY_PERIODS: u32 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly
+}
+
+#[derive(Debug, Clone)]
+pub struct RRule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub count: Option<u32>,
+    /// The last possible instance, in the event's wall time
+    pub until: Option<NaiveDateTime>,
+    /// Weekdays with an optional position, like 2MO (second monday) or -1FR (last friday)
+    pub by_day: Vec<(Option<i32>, Weekday)>,
+    /// Days of the month, negative ones count from the end
+    pub by_month_day: Vec<i32>,
+    pub by_month: Vec<u32>,
+    /// Which of the period's instances to keep, negative ones count from the end
+    pub by_set_pos: Vec<i32>,
+    pub week_start: Weekday
+}
+
+fn parse_weekday(text: &str) -> Option<Weekday> {
+    match text {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None
+    }
+}
+
+/// Parses a weekday with an optional position, like MO, 2MO or -1FR
+fn parse_by_day(text: &str) -> Option<(Option<i32>, Weekday)> {
+    let split = text.len().checked_sub(2)?;
+    let weekday = parse_weekday(text.get(split..)?)?;
+
+    match &text[..split] {
+        "" => Some((None, weekday)),
+        position => Some((Some(position.trim_start_matches('+').parse().ok()?), weekday))
+    }
+}
+
+fn parse_list<T: std::str::FromStr>(text: &str) -> Vec<T> {
+    text.split(',').filter_map(|v| v.trim_start_matches('+').parse().ok()).collect()
+}
+
+/// The day of the month from a position, negative positions count from the end. None if the month is too short
+fn nth_day_of_month(year: i32, month: u32, day: i32) -> Option<NaiveDate> {
+    let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+    let length = (first + Months::new(1) - first).num_days() as i32;
+
+    let day = if day < 0 { length + day + 1 } else { day };
+    if day < 1 || day > length {
+        return None;
+    }
+
+    NaiveDate::from_ymd_opt(year, month, day as u32)
+}
+
+/// Every `weekday` of the month, or only the one at `position` if it's set
+fn weekdays_of_month(year: i32, month: u32, position: Option<i32>, weekday: Weekday) -> Vec<NaiveDate> {
+    let Some(first) = NaiveDate::from_ymd_opt(year, month, 1) else {
+        return Vec::new();
+    };
+
+    let all: Vec<NaiveDate> = first
+        .iter_days()
+        .take_while(|d| d.month() == month)
+        .filter(|d| d.weekday() == weekday)
+        .collect();
+
+    match position {
+        None => all,
+        Some(p) if p > 0 => all.get(p as usize - 1).copied().into_iter().collect(),
+        Some(p) => all.len().checked_sub(p.unsigned_abs() as usize).and_then(|i| all.get(i).copied()).into_iter().collect()
+    }
+}
+
+impl RRule {
+    /// Parses the value of an RRULE property. `until` converts the UNTIL value to the event's wall time.
+    /// Fails if there is no FREQ, or it's more frequent than daily
+    pub fn parse(value: &str, until: impl Fn(&str) -> Option<NaiveDateTime>) -> Result<Self, Box<dyn Error>> {
+        let mut rule = RRule {
+            frequency: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_month: Vec::new(),
+            by_set_pos: Vec::new(),
+            week_start: Weekday::Mon
+        };
+        let mut has_frequency = false;
+
+        for part in value.split(';') {
+            let Some((key, value)) = part.split_once('=') else {
+                continue;
+            };
+
+            match key.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    rule.frequency = match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        // Hourly and more frequent rules arent supported
+                        other => return Err(format!("FREQ={other} isnt supported").into())
+                    };
+                    has_frequency = true;
+                },
+                "INTERVAL" => rule.interval = value.parse().unwrap_or(1).max(1),
+                "COUNT" => rule.count = value.parse().ok(),
+                "UNTIL" => rule.until = until(value),
+                "BYDAY" => rule.by_day = value.split(',').filter_map(parse_by_day).collect(),
+                "BYMONTHDAY" => rule.by_month_day = parse_list(value),
+                "BYMONTH" => rule.by_month = parse_list(value),
+                "BYSETPOS" => rule.by_set_pos = parse_list(value),
+                "WKST" => rule.week_start = parse_weekday(value).unwrap_or(Weekday::Mon),
+                _ => {}
+            }
+        }
+
+        match has_frequency {
+            true => Ok(rule),
+            false => Err("it has no FREQ".into())
+        }
+    }
+
+    /// The days of a month that match the BYMONTHDAY and BYDAY parts.
+    /// `default_day` is used when neither is set
+    fn days_of_month(&self, year: i32, month: u32, default_day: u32) -> Vec<NaiveDate> {
+        if !self.by_month_day.is_empty() {
+            return self.by_month_day
+                .iter()
+                .filter_map(|&d| nth_day_of_month(year, month, d))
+                .filter(|d| self.by_day.is_empty() || self.by_day.iter().any(|(_, w)| *w == d.weekday()))
+                .collect();
+        }
+
+        if !self.by_day.is_empty() {
+            return self.by_day
+                .iter()
+                .flat_map(|&(position, weekday)| weekdays_of_month(year, month, position, weekday))
+                .collect();
+        }
+
+        nth_day_of_month(year, month, default_day as i32).into_iter().collect()
+    }
+
+    /// The candidate days of the n-th period after the one `start` is in
+    fn period_days(&self, start: NaiveDate, n: u32) -> Vec<NaiveDate> {
+        let mut days = match self.frequency {
+            Frequency::Daily => {
+                let day = start + Duration::days(n as i64 * self.interval as i64);
+                let matches_month_day = self.by_month_day.is_empty() ||
+                    self.by_month_day.iter().any(|&d| nth_day_of_month(day.year(), day.month(), d) == Some(day));
+                let matches_weekday = self.by_day.is_empty() || self.by_day.iter().any(|(_, w)| *w == day.weekday());
+
+                if matches_month_day && matches_weekday { vec![day] } else { Vec::new() }
+            },
+            Frequency::Weekly => {
+                let week_start = start - Duration::days(start.weekday().days_since(self.week_start) as i64);
+                let week = week_start + Duration::weeks(n as i64 * self.interval as i64);
+
+                week.iter_days()
+                    .take(7)
+                    .filter(|d| match self.by_day.is_empty() {
+                        true => d.weekday() == start.weekday(),
+                        false => self.by_day.iter().any(|(_, w)| *w == d.weekday())
+                    })
+                    .collect()
+            },
+            Frequency::Monthly => {
+                let Some(month) = start.with_day(1).and_then(|d| d.checked_add_months(Months::new(n * self.interval))) else {
+                    return Vec::new();
+                };
+                self.days_of_month(month.year(), month.month(), start.day())
+            },
+            Frequency::Yearly => {
+                let year = start.year() + (n * self.interval) as i32;
+                let months = if self.by_month.is_empty() { vec![start.month()] } else { self.by_month.clone() };
+
+                months.iter().flat_map(|&m| self.days_of_month(year, m, start.day())).collect()
+            }
+        };
+
+        if !self.by_month.is_empty() {
+            days.retain(|d| self.by_month.contains(&d.month()));
+        }
+
+        days.sort();
+        days.dedup();
+
+        if !self.by_set_pos.is_empty() {
+            let len = days.len() as i32;
+            days = self.by_set_pos
+                .iter()
+                .filter_map(|&p| {
+                    let i = if p < 0 { len + p } else { p - 1 };
+                    days.get(usize::try_from(i).ok()?).copied()
+                })
+                .collect();
+            days.sort();
+        }
+
+        days
+    }
+
+    /// Every instance of the rule from `start` (the DTSTART), in order. Infinite if there is no COUNT or UNTIL
+    pub fn instances(&self, start: NaiveDateTime) -> impl Iterator<Item = NaiveDateTime> + '_ {
+        let mut period = 0;
+        let mut empty_periods = 0;
+        let mut pending: Vec<NaiveDateTime> = Vec::new();
+        let mut produced = 0;
+
+        std::iter::from_fn(move || {
+            while pending.is_empty() {
+                if empty_periods > MAX_EMPTY_PERIODS {
+                    return None;
+                }
+
+                pending = self.period_days(start.date(), period)
+                    .into_iter()
+                    .map(|d| d.and_time(start.time()))
+                    .filter(|t| *t >= start)
+                    .rev()
+                    .collect();
+                period += 1;
+
+                if pending.is_empty() { empty_periods += 1 } else { empty_periods = 0 }
+            }
+
+            let next = pending.pop()?;
+
+            if self.count.is_some_and(|c| produced >= c) || self.until.is_some_and(|u| next > u) {
+                return None;
+            }
+            produced += 1;
+
+            Some(next)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(y: i32, m: u32, d: u32, h: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap().and_hms_opt(h, 0, 0).unwrap()
+    }
+
+    fn rule(value: &str) -> RRule {
+        RRule::parse(value, |until| NaiveDateTime::parse_from_str(until, "%Y%m%dT%H%M%S").ok()).unwrap()
+    }
+
+    fn first(rule: &RRule, start: NaiveDateTime, n: usize) -> Vec<NaiveDateTime> {
+        rule.instances(start).take(n).collect()
+    }
+
+    #[test]
+    fn rejects_unsupported_frequencies() {
+        assert!(RRule::parse("FREQ=HOURLY;INTERVAL=2", |_| None).is_err());
+        assert!(RRule::parse("FREQ=MINUTELY", |_| None).is_err());
+        assert!(RRule::parse("INTERVAL=2;COUNT=3", |_| None).is_err());
+    }
+
+    #[test]
+    fn weekly_by_day_with_interval() {
+        // Every other week on tuesday and thursday, starting on a thursday
+        let rule = rule("FREQ=WEEKLY;INTERVAL=2;BYDAY=TU,TH");
+        assert_eq!(first(&rule, at(2026, 1, 8, 10), 4), vec![
+            at(2026, 1, 8, 10), at(2026, 1, 20, 10), at(2026, 1, 22, 10), at(2026, 2, 3, 10)
+        ]);
+    }
+
+    #[test]
+    fn weekly_with_week_start() {
+        // With WKST=SU the sunday belongs to the next monday's week, so INTERVAL=2 skips differently
+        let monday = rule("FREQ=WEEKLY;INTERVAL=2;BYDAY=TU,SU;WKST=MO");
+        let sunday = rule("FREQ=WEEKLY;INTERVAL=2;BYDAY=TU,SU;WKST=SU");
+        assert_eq!(first(&monday, at(1997, 8, 5, 9), 4), vec![
+            at(1997, 8, 5, 9), at(1997, 8, 10, 9), at(1997, 8, 19, 9), at(1997, 8, 24, 9)
+        ]);
+        assert_eq!(first(&sunday, at(1997, 8, 5, 9), 4), vec![
+            at(1997, 8, 5, 9), at(1997, 8, 17, 9), at(1997, 8, 19, 9), at(1997, 8, 31, 9)
+        ]);
+    }
+
+    #[test]
+    fn monthly_by_positioned_day() {
+        let second_tuesday = rule("FREQ=MONTHLY;BYDAY=2TU");
+        assert_eq!(first(&second_tuesday, at(2026, 1, 13, 18), 3), vec![
+            at(2026, 1, 13, 18), at(2026, 2, 10, 18), at(2026, 3, 10, 18)
+        ]);
+
+        let last_friday = rule("FREQ=MONTHLY;BYDAY=-1FR;COUNT=2");
+        assert_eq!(first(&last_friday, at(2026, 1, 30, 18), 5), vec![at(2026, 1, 30, 18), at(2026, 2, 27, 18)]);
+    }
+
+    #[test]
+    fn monthly_by_set_pos() {
+        // The second to last weekday of the month
+        let rule = rule("FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-2");
+        assert_eq!(first(&rule, at(2026, 1, 1, 9), 3), vec![
+            at(2026, 1, 29, 9), at(2026, 2, 26, 9), at(2026, 3, 30, 9)
+        ]);
+    }
+
+    #[test]
+    fn monthly_skips_short_months() {
+        let day_31 = rule("FREQ=MONTHLY;BYMONTHDAY=31");
+        assert_eq!(first(&day_31, at(2026, 1, 31, 12), 3), vec![
+            at(2026, 1, 31, 12), at(2026, 3, 31, 12), at(2026, 5, 31, 12)
+        ]);
+
+        let last_day = rule("FREQ=MONTHLY;BYMONTHDAY=-1");
+        assert_eq!(first(&last_day, at(2026, 1, 31, 12), 3), vec![
+            at(2026, 1, 31, 12), at(2026, 2, 28, 12), at(2026, 3, 31, 12)
+        ]);
+    }
+
+    #[test]
+    fn yearly_by_month_and_day() {
+        // Thanksgiving
+        let rule = rule("FREQ=YEARLY;BYMONTH=11;BYDAY=4TH");
+        assert_eq!(first(&rule, at(2026, 11, 26, 0), 3), vec![
+            at(2026, 11, 26, 0), at(2027, 11, 25, 0), at(2028, 11, 23, 0)
+        ]);
+    }
+
+    #[test]
+    fn stops_at_count_and_until() {
+        let count = rule("FREQ=DAILY;COUNT=3");
+        assert_eq!(count.instances(at(2026, 1, 1, 8)).count(), 3);
+
+        // UNTIL is inclusive
+        let until = rule("FREQ=DAILY;INTERVAL=2;UNTIL=20260107T080000");
+        assert_eq!(until.instances(at(2026, 1, 1, 8)).collect::<Vec<_>>(), vec![
+            at(2026, 1, 1, 8), at(2026, 1, 3, 8), at(2026, 1, 5, 8), at(2026, 1, 7, 8)
+        ]);
+    }
+
+    #[test]
+    fn ends_when_nothing_matches() {
+        // There is no february 30th
+        let rule = rule("FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30");
+        assert_eq!(rule.instances(at(2026, 1, 1, 0)).count(), 0);
+    }
+}
@@ -0,0 +1,89 @@
+use gtk::glib;
+use futures::future::{ self, Either };
+
+use std::pin::pin;
+use std::time::Duration;
+
+use super::{ Clock, ClockFormat };
+use super::ticker::{ Scheduler, SystemTime, Ticker };
+
+/// A digit cell as the driver last left it
+#[derive(Debug, Clone)]
+struct Digit {
+    text: String,
+    /// The layer currently shown, 1 or 2
+    visible_layer: u32
+}
+
+/// Animates, and drives the clock state changes.
+/// On every tick only the digits that changed are cross faded to their new value.
+/// While fast_ticks is set it ticks every second, even if the time doesnt have seconds
+pub async fn drive_clock(state: Clock, format: ClockFormat) {
+    let ticker = Ticker::new(Scheduler::new(SystemTime::new(), format.tick_period()));
+    let second_ticker = Ticker::new(Scheduler::new(SystemTime::new(), Duration::from_secs(1)));
+    let pattern = format.pattern();
+
+    // Wakes up the driver when fast_ticks changes, so a timer started mid minute is shown right away,
+    // and when the time zone changes or the system wakes up, so the new time is shown right away
+    let (wake_snd, wake_rcv) = async_channel::bounded::<()>(1);
+    state.connect_fast_ticks_notify(glib::clone!(
+        #[strong]
+        wake_snd,
+        move |_| {
+            let _ = wake_snd.try_send(());
+        }
+    ));
+    state.connect_time_changed(move |_| {
+        let _ = wake_snd.try_send(());
+    });
+
+    let mut now = ticker.now();
+    let mut digits: Vec<Digit> = Vec::new();
+
+    loop {
+        let label = now.format(&pattern).to_string();
+
+        // A tick can come early when the system time changes, only animate if the time shown changed
+        if label != state.label() {
+            let chars: Vec<String> = label.chars().map(|c| c.to_string()).collect();
+
+            // The number of cells changed (like 9:59 -> 10:00), every digit starts out empty
+            if chars.len() != digits.len() {
+                digits = vec![Digit { text: String::new(), visible_layer: 2 }; chars.len()];
+                state.set_digit_count(chars.len() as u32);
+            }
+
+            let changed: Vec<usize> = (0..chars.len())
+                .filter(|&i| digits[i].text != chars[i])
+                .collect();
+
+            for &i in &changed {
+                let hidden_layer = 3 - digits[i].visible_layer;
+                state.set_digit_label(i as u32, hidden_layer, &chars[i]);
+                state.digit_should_fade(i as u32, hidden_layer, false);
+            }
+
+            state.set_label(label);
+
+            glib::timeout_future(Duration::from_millis(50)).await;
+
+            for &i in &changed {
+                state.digit_should_fade(i as u32, digits[i].visible_layer, true);
+                digits[i].visible_layer = 3 - digits[i].visible_layer;
+                digits[i].text = chars[i].clone();
+            }
+        }
+
+        state.tick();
+
+        let tick = match state.fast_ticks() {
+            true => second_ticker.tick(),
+            false => ticker.tick()
+        };
+
+        now = match future::select(pin!(tick), pin!(wake_rcv.recv())).await {
+            Either::Left((now, _)) => now,
+            Either::Right(_) => ticker.now()
+        };
+    }
+}
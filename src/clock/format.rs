@@ -0,0 +1,109 @@
+use chrono::format::{ Fixed, Item, Numeric, StrftimeItems };
+
+use std::time::Duration;
+
+/// 24 or 12 hour clock
+#[derive(Debug, Clone, PartialEq)]
+pub enum HourFormat {
+    H24,
+    H12
+}
+
+/// How the clock shows the time
+#[derive(Debug, Clone)]
+pub struct ClockFormat {
+    pub hours: HourFormat,
+    pub seconds: bool,
+    /// Show AM/PM after the time, only used with the 12 hour clock
+    pub am_pm: bool,
+    /// A strftime pattern used instead of the options above
+    pub custom: Option<String>
+}
+
+impl Default for ClockFormat {
+    fn default() -> Self {
+        ClockFormat { hours: HourFormat::H24, seconds: true, am_pm: true, custom: None }
+    }
+}
+
+impl ClockFormat {
+    /// The strftime pattern for the clock's label
+    pub fn pattern(&self) -> String {
+        if let Some(custom) = &self.custom {
+            return custom.clone();
+        }
+
+        let mut pattern = match self.hours {
+            HourFormat::H24 => "%H:%M".to_string(),
+            HourFormat::H12 => "%-I:%M".to_string()
+        };
+
+        if self.seconds {
+            pattern.push_str(":%S");
+        }
+
+        if self.hours == HourFormat::H12 && self.am_pm {
+            pattern.push_str(" %p");
+        }
+
+        pattern
+    }
+
+    /// The pattern without the seconds, used by the smaller clocks (like the world clocks).
+    /// Custom patterns fall back to the 24 hour clock
+    pub fn minutes_pattern(&self) -> String {
+        ClockFormat { seconds: false, custom: None, ..self.clone() }.pattern()
+    }
+
+    /// True if the label changes every second
+    pub fn shows_seconds(&self) -> bool {
+        match &self.custom {
+            // chrono expands the composite ones (%T, %c, %r, ...) to their parts, padded ones (%_S, %0S) included
+            Some(custom) => StrftimeItems::new(custom).any(|item| match item {
+                Item::Numeric(numeric, _) => matches!(
+                    numeric,
+                    Numeric::Second | Numeric::Timestamp | Numeric::Nanosecond
+                ),
+                Item::Fixed(fixed) => matches!(
+                    fixed,
+                    Fixed::RFC2822 | Fixed::RFC3339 | Fixed::Nanosecond | Fixed::Nanosecond3 | Fixed::Nanosecond6 | Fixed::Nanosecond9
+                ),
+                _ => false
+            }),
+            None => self.seconds
+        }
+    }
+
+    /// How often the clock needs to be updated
+    pub fn tick_period(&self) -> Duration {
+        if self.shows_seconds() {
+            Duration::from_secs(1)
+        } else {
+            Duration::from_secs(60)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn custom(pattern: &str) -> ClockFormat {
+        ClockFormat { custom: Some(pattern.to_string()), ..ClockFormat::default() }
+    }
+
+    #[test]
+    fn custom_patterns_with_seconds() {
+        for pattern in ["%S", "%-S", "%_S", "%0S", "%T", "%X", "%r", "%c", "%+", "%s", "%H.%M.%S%.3f"] {
+            assert!(custom(pattern).shows_seconds(), "{pattern} shows seconds");
+        }
+    }
+
+    #[test]
+    fn custom_patterns_without_seconds() {
+        for pattern in ["%H:%M", "%R", "%-I:%M %p", "%a %H:%M", "%%S"] {
+            assert!(!custom(pattern).shows_seconds(), "{pattern} doesnt show seconds");
+        }
+        assert_eq!(custom("%R").tick_period(), Duration::from_secs(60));
+    }
+}
@@ -1,4 +1,4 @@
-use std::cell::RefCell;
+use std::cell::{ Cell, RefCell };
 
 use std::sync::OnceLock;
 use glib::subclass::Signal;
@@ -11,19 +11,41 @@ use gtk::subclass::prelude::*;
 #[derive(Default, Properties)]
 #[properties[wrapper_type = super::Clock]]
 pub struct Clock {
+    /// The whole time currently shown
     #[property(get, set)]
-    clock1_label: RefCell<String>,
+    label: RefCell<String>,
+    /// How many characters (digit cells) the time has
     #[property(get, set)]
-    clock2_label: RefCell<String>
+    digit_count: Cell<u32>,
+    /// Tick every second even if the time doesnt have seconds (while a timer or the stopwatch runs)
+    #[property(get, set)]
+    fast_ticks: Cell<bool>,
+    /// False until timedated was asked if the system clock is synchronized
+    #[property(get, set)]
+    sync_known: Cell<bool>,
+    /// The system clock is synchronized with a time server
+    #[property(get, set)]
+    synced: Cell<bool>,
+    /// Time synchronization (like systemd-timesyncd) is turned on
+    #[property(get, set)]
+    ntp_enabled: Cell<bool>
 }
 
 impl Clock {
-    pub fn clock1_should_fade(&self, should_fade: bool) {
-        self.obj().emit_by_name::<()>("clock1-should-fade", &[&should_fade]);
+    pub fn set_digit_label(&self, index: u32, layer: u32, label: &str) {
+        self.obj().emit_by_name::<()>("digit-label", &[&index, &layer, &label]);
     }
 
-    pub fn clock2_should_fade(&self, should_fade: bool) {
-        self.obj().emit_by_name::<()>("clock2-should-fade", &[&should_fade]);
+    pub fn digit_should_fade(&self, index: u32, layer: u32, should_fade: bool) {
+        self.obj().emit_by_name::<()>("digit-should-fade", &[&index, &layer, &should_fade]);
+    }
+
+    pub fn tick(&self) {
+        self.obj().emit_by_name::<()>("tick", &[]);
+    }
+
+    pub fn time_changed(&self) {
+        self.obj().emit_by_name::<()>("time-changed", &[]);
     }
 }
 
@@ -38,14 +60,21 @@ impl ObjectImpl for Clock {
     fn signals() -> &'static [Signal] {
         static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
         SIGNALS.get_or_init(|| {
+            // Every digit has 2 layers (1 and 2) that are cross faded, like the old clock1 and clock2 labels
             vec![
-                Signal::builder("clock1-should-fade")
-                    .param_types([bool::static_type()])
+                // index, layer, label
+                Signal::builder("digit-label")
+                    .param_types([u32::static_type(), u32::static_type(), String::static_type()])
                     .build(),
-                Signal::builder("clock2-should-fade")
-                    .param_types([bool::static_type()])
+                // index, layer, should_fade
+                Signal::builder("digit-should-fade")
+                    .param_types([u32::static_type(), u32::static_type(), bool::static_type()])
                     .build(),
+                // Emitted on every tick of the driver, even if the label didnt change
+                Signal::builder("tick").build(),
+                // The system woke up from a suspend or the time zone changed, the time shown should be refreshed right away
+                Signal::builder("time-changed").build(),
             ]
         })
     }
-}
\ No newline at end of file
+}
@@ -1,8 +1,17 @@
 use glib::Object;
-use gtk::glib::{self, subclass::types::ObjectSubclassIsExt};
+use gtk::glib::{self, prelude::*, subclass::types::ObjectSubclassIsExt};
 
 mod imp;
 
+mod format;
+pub use format::{ ClockFormat, HourFormat };
+
+pub mod ticker;
+mod until;
+pub use until::{ days_until, next_midnight, time_until };
+mod driver;
+pub use driver::drive_clock;
+
 glib::wrapper!{
     pub struct Clock(ObjectSubclass<imp::Clock>);
 }
@@ -10,16 +19,40 @@ glib::wrapper!{
 impl Clock {
     pub fn new() -> Self {
         Object::builder()
-        .property("clock1_label", String::new())
-        .property("clock2_label", String::new())
+        .property("label", String::new())
+        .property("digit_count", 0u32)
+        .property("fast_ticks", false)
+        .property("sync_known", false)
+        .property("synced", false)
+        .property("ntp_enabled", false)
         .build()
     }
 
-    pub fn clock1_should_fade(&self, new_state: bool) {
-        self.imp().clock1_should_fade(new_state);
+    /// Sets the text of one of the digit's layers (1 or 2)
+    pub fn set_digit_label(&self, index: u32, layer: u32, label: &str) {
+        self.imp().set_digit_label(index, layer, label);
+    }
+
+    /// Fades one of the digit's layers (1 or 2) out or back in
+    pub fn digit_should_fade(&self, index: u32, layer: u32, new_state: bool) {
+        self.imp().digit_should_fade(index, layer, new_state);
+    }
+
+    /// Tells everything that follows the clock (like the timers) that it ticked
+    pub fn tick(&self) {
+        self.imp().tick();
+    }
+
+    pub fn connect_tick<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure("tick", false, glib::closure_local!(move |clock: &Self| f(clock)))
+    }
+
+    /// Tells the clock (and the date) to refresh now, because the system woke up or the time zone changed
+    pub fn time_changed(&self) {
+        self.imp().time_changed();
     }
 
-    pub fn clock2_should_fade(&self, new_state: bool) {
-        self.imp().clock2_should_fade(new_state);
+    pub fn connect_time_changed<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure("time-changed", false, glib::closure_local!(move |clock: &Self| f(clock)))
     }
 }
\ No newline at end of file
@@ -0,0 +1,302 @@
+use chrono::{ DateTime, Local, TimeZone, Utc };
+use gtk::glib::{ self, ControlFlow, IOCondition };
+
+use std::io;
+use std::os::fd::{ AsRawFd, FromRawFd, OwnedFd };
+use std::time::Duration;
+
+// The clock ticks on the wall clock's second (or minute) boundaries. Every tick is scheduled
+// against the current wall clock time, so sleeping late never accumulates into drift.
+//
+// Sleeping is done with a timerfd on CLOCK_REALTIME with an absolute deadline, which (unlike glib's
+// monotonic timeouts) fires right away after a suspend, and wakes up when the system time is changed.
+
+/// Where the scheduler gets the current time from, and how it sleeps on it, so it can be driven by a fake clock
+pub trait TimeSource {
+    fn now(&self) -> DateTime<Local>;
+
+    /// Sleeps until `deadline`, or until the time is changed
+    async fn sleep_until(&self, deadline: DateTime<Local>) -> Wakeup;
+}
+
+/// The system's wall clock
+pub struct SystemTime {
+    /// None if the timerfd couldnt be created, then glib's timeouts are used
+    timer: Option<WallClockTimer>
+}
+
+impl SystemTime {
+    pub fn new() -> Self {
+        let timer = match WallClockTimer::new() {
+            Ok(t) => Some(t),
+            Err(e) => {
+                eprintln!("Failed to create a wall clock timer, the clock might lag behind after a suspend: {e}");
+                None
+            }
+        };
+
+        SystemTime { timer }
+    }
+}
+
+impl Default for SystemTime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimeSource for SystemTime {
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+
+    async fn sleep_until(&self, deadline: DateTime<Local>) -> Wakeup {
+        if let Some(timer) = &self.timer {
+            match timer.sleep_until(deadline).await {
+                Ok(wakeup) => return wakeup,
+                Err(e) => eprintln!("Wall clock timer failed: {e}")
+            }
+        }
+
+        glib::timeout_future((deadline - self.now()).to_std().unwrap_or_default()).await;
+        Wakeup::Deadline
+    }
+}
+
+/// Decides when the clock should tick: on every multiple of `period` since the unix epoch
+pub struct Scheduler<T: TimeSource> {
+    time: T,
+    period: Duration
+}
+
+impl<T: TimeSource> Scheduler<T> {
+    pub fn new(time: T, period: Duration) -> Self {
+        Scheduler { time, period }
+    }
+
+    pub fn now(&self) -> DateTime<Local> {
+        self.time.now()
+    }
+
+    /// The first tick strictly after `now`
+    pub fn next_tick_after(&self, now: DateTime<Local>) -> DateTime<Local> {
+        let period = self.period.as_millis().max(1) as i64;
+        let now_millis = now.timestamp_millis();
+        let next = (now_millis.div_euclid(period) + 1) * period;
+
+        Local.timestamp_millis_opt(next).earliest().unwrap_or(now)
+    }
+
+    /// The next tick, measured from the current time
+    pub fn next_tick(&self) -> DateTime<Local> {
+        self.next_tick_after(self.now())
+    }
+}
+
+/// Why a sleep ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wakeup {
+    /// The deadline was reached (or it passed during a suspend)
+    Deadline,
+    /// The system time was changed, the deadline should be recalculated
+    ClockChanged
+}
+
+/// A timer that sleeps until a wall clock time
+pub struct WallClockTimer {
+    fd: OwnedFd
+}
+
+impl WallClockTimer {
+    pub fn new() -> io::Result<Self> {
+        let fd = unsafe { libc::timerfd_create(libc::CLOCK_REALTIME, libc::TFD_NONBLOCK | libc::TFD_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // SAFETY: the fd was just created, and nothing else owns it
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+        Ok(WallClockTimer { fd })
+    }
+
+    fn arm(&self, deadline: DateTime<Utc>) -> io::Result<()> {
+        let spec = libc::itimerspec {
+            it_interval: libc::timespec { tv_sec: 0, tv_nsec: 0 },
+            it_value: libc::timespec {
+                tv_sec: deadline.timestamp() as libc::time_t,
+                tv_nsec: deadline.timestamp_subsec_nanos() as libc::c_long
+            }
+        };
+
+        let flags = libc::TFD_TIMER_ABSTIME | libc::TFD_TIMER_CANCEL_ON_SET;
+        let res = unsafe { libc::timerfd_settime(self.fd.as_raw_fd(), flags, &spec, std::ptr::null_mut()) };
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Sleeps until `deadline` on the wall clock, or until the system time is changed
+    pub async fn sleep_until<Tz: TimeZone>(&self, deadline: DateTime<Tz>) -> io::Result<Wakeup> {
+        self.arm(deadline.with_timezone(&Utc))?;
+
+        loop {
+            let (sender, receiver) = futures::channel::oneshot::channel();
+            let mut sender = Some(sender);
+            let mut source = SourceGuard(Some(glib::unix_fd_add_local(self.fd.as_raw_fd(), IOCondition::IN, move |_, _| {
+                if let Some(sender) = sender.take() {
+                    let _ = sender.send(());
+                }
+                ControlFlow::Break
+            })));
+            let _ = receiver.await;
+            // The source removed itself by returning Break
+            source.0 = None;
+
+            // Reading the expiration count is what tells the two wakeups apart
+            let mut expirations = [0u8; 8];
+            let res = unsafe {
+                libc::read(self.fd.as_raw_fd(), expirations.as_mut_ptr() as *mut libc::c_void, expirations.len())
+            };
+
+            if res >= 0 {
+                return Ok(Wakeup::Deadline);
+            }
+
+            let err = io::Error::last_os_error();
+            match err.raw_os_error() {
+                Some(libc::ECANCELED) => return Ok(Wakeup::ClockChanged),
+                // Spurious wakeup, keep waiting
+                Some(libc::EAGAIN) => continue,
+                _ => return Err(err)
+            }
+        }
+    }
+}
+
+/// Removes a glib source when it's dropped. The fd source is attached for every sleep, and it has to be
+/// removed if the sleep is cancelled (its future dropped), or the next wakeup would fire all the old ones too
+struct SourceGuard(Option<glib::SourceId>);
+
+impl Drop for SourceGuard {
+    fn drop(&mut self) {
+        if let Some(source) = self.0.take() {
+            source.remove();
+        }
+    }
+}
+
+/// Waits for the ticks of a scheduler
+pub struct Ticker<T: TimeSource> {
+    scheduler: Scheduler<T>
+}
+
+impl<T: TimeSource> Ticker<T> {
+    pub fn new(scheduler: Scheduler<T>) -> Self {
+        Ticker { scheduler }
+    }
+
+    pub fn now(&self) -> DateTime<Local> {
+        self.scheduler.now()
+    }
+
+    /// Waits until the next tick, and returns the current time.
+    /// Returns early if the system time changes, so the clock can be refreshed right away
+    pub async fn tick(&self) -> DateTime<Local> {
+        self.scheduler.time.sleep_until(self.scheduler.next_tick()).await;
+        self.scheduler.now()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use futures::executor::block_on;
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+
+    /// A whole minute since the epoch, so the minute boundaries are easy to see
+    const T: i64 = 1_789_999_980;
+
+    fn at(seconds: i64, millis: u32) -> DateTime<Local> {
+        Utc.timestamp_opt(seconds, millis * 1_000_000).unwrap().with_timezone(&Local)
+    }
+
+    /// A clock that only moves when it's slept on: to the deadline, or to where the next scripted jump says
+    struct FakeTime {
+        now: RefCell<DateTime<Local>>,
+        /// Where the next sleeps end instead of their deadline, like a suspend or the time being set
+        jumps: RefCell<VecDeque<(DateTime<Local>, Wakeup)>>,
+        deadlines: RefCell<Vec<DateTime<Local>>>
+    }
+
+    impl FakeTime {
+        fn new(now: DateTime<Local>, jumps: Vec<(DateTime<Local>, Wakeup)>) -> Self {
+            FakeTime { now: RefCell::new(now), jumps: RefCell::new(jumps.into()), deadlines: RefCell::new(Vec::new()) }
+        }
+    }
+
+    impl TimeSource for FakeTime {
+        fn now(&self) -> DateTime<Local> {
+            *self.now.borrow()
+        }
+
+        async fn sleep_until(&self, deadline: DateTime<Local>) -> Wakeup {
+            self.deadlines.borrow_mut().push(deadline);
+
+            let (now, wakeup) = self.jumps.borrow_mut().pop_front().unwrap_or((deadline, Wakeup::Deadline));
+            self.now.replace(now);
+            wakeup
+        }
+    }
+
+    fn scheduler(period: u64) -> Scheduler<FakeTime> {
+        Scheduler::new(FakeTime::new(at(T, 0), Vec::new()), Duration::from_secs(period))
+    }
+
+    #[test]
+    fn ticks_on_the_next_second() {
+        let scheduler = scheduler(1);
+        assert_eq!(scheduler.next_tick_after(at(T, 300)), at(T + 1, 0));
+        assert_eq!(scheduler.next_tick_after(at(T, 999)), at(T + 1, 0));
+    }
+
+    #[test]
+    fn ticks_after_an_exact_boundary() {
+        let scheduler = scheduler(1);
+        assert_eq!(scheduler.next_tick_after(at(T + 1, 0)), at(T + 2, 0));
+        assert_eq!(scheduler.next_tick(), at(T + 1, 0));
+    }
+
+    #[test]
+    fn ticks_on_the_minute_without_seconds() {
+        let scheduler = scheduler(60);
+        assert_eq!(scheduler.next_tick_after(at(T + 56, 700)), at(T + 60, 0));
+        assert_eq!(scheduler.next_tick_after(at(T + 60, 0)), at(T + 120, 0));
+        assert_eq!(scheduler.next_tick_after(at(T + 59, 999)), at(T + 60, 0));
+    }
+
+    #[test]
+    fn follows_the_time_jumping_forward() {
+        // Suspended for 5 minutes, the timer fires late on waking up
+        let time = FakeTime::new(at(T, 300), vec![(at(T + 300, 200), Wakeup::Deadline)]);
+        let ticker = Ticker::new(Scheduler::new(time, Duration::from_secs(1)));
+
+        assert_eq!(block_on(ticker.tick()), at(T + 300, 200));
+        assert_eq!(block_on(ticker.tick()), at(T + 301, 0));
+        assert_eq!(*ticker.scheduler.time.deadlines.borrow(), vec![at(T + 1, 0), at(T + 301, 0)]);
+    }
+
+    #[test]
+    fn follows_the_time_jumping_backwards() {
+        // The time is set an hour back while sleeping on a minute tick
+        let time = FakeTime::new(at(T + 30, 0), vec![(at(T - 3600 + 10, 500), Wakeup::ClockChanged)]);
+        let ticker = Ticker::new(Scheduler::new(time, Duration::from_secs(60)));
+
+        assert_eq!(block_on(ticker.tick()), at(T - 3600 + 10, 500));
+        assert_eq!(block_on(ticker.tick()), at(T - 3600 + 60, 0));
+        assert_eq!(*ticker.scheduler.time.deadlines.borrow(), vec![at(T + 60, 0), at(T - 3600 + 60, 0)]);
+    }
+}
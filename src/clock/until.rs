@@ -0,0 +1,137 @@
+use chrono::{ DateTime, Days, LocalResult, NaiveDateTime, TimeZone };
+
+use std::time::Duration;
+
+// Wall clock times dont always map to a single instant. When DST starts an hour is skipped
+// (in some zones it's the one starting at midnight), and when it ends an hour happens twice.
+// So a day isnt always 24 hours, and the time until something has to be measured between instants.
+// These take any time zone so they can be tested with zones that have DST, they are used with Local.
+
+/// The instant of a wall clock time. A time skipped by a DST change moves to the first one after the gap,
+/// a time that happens twice is the earlier one
+pub fn resolve_local<Tz: TimeZone>(tz: &Tz, time: NaiveDateTime) -> Option<DateTime<Tz>> {
+    // The gaps are an hour at most (half an hour in some zones), so this finds the end of it
+    for minutes in 0..=2 * 60 {
+        let candidate = time + chrono::Duration::minutes(minutes);
+        match tz.from_local_datetime(&candidate) {
+            LocalResult::Single(t) => return Some(t),
+            LocalResult::Ambiguous(earliest, _) => return Some(earliest),
+            LocalResult::None => continue
+        }
+    }
+
+    None
+}
+
+/// The start of the next day
+pub fn next_midnight<Tz: TimeZone>(now: DateTime<Tz>) -> DateTime<Tz> {
+    now.date_naive()
+        .succ_opt()
+        .and_then(|day| resolve_local(&now.timezone(), day.and_hms_opt(0, 0, 0)?))
+        // Only at the end of time
+        .unwrap_or(now)
+}
+
+/// The time left until `target`, zero if it's already passed
+pub fn time_until<Tz: TimeZone>(target: DateTime<Tz>, now: DateTime<Tz>) -> Duration {
+    (target - now).to_std().unwrap_or_default()
+}
+
+/// The time left until a wall clock time, as whole days and the rest. The days are counted back
+/// from the target on the calendar, so it's "N days 00:00" at the target's time of day even across a DST change.
+/// None if it's already passed
+pub fn days_until<Tz: TimeZone>(target: NaiveDateTime, now: DateTime<Tz>) -> Option<(u64, Duration)> {
+    let tz = now.timezone();
+    let target_instant = resolve_local(&tz, target)?;
+    if target_instant <= now {
+        return None;
+    }
+
+    // The same time of day, `days` days before the target
+    let day_start = |days: u64| target.checked_sub_days(Days::new(days)).and_then(|time| resolve_local(&tz, time));
+
+    let mut days = (target.date() - now.date_naive()).num_days().max(0) as u64;
+    while days > 0 && day_start(days).is_none_or(|start| start < now) {
+        days -= 1;
+    }
+
+    // The rest is the real time until the last whole day starts
+    let rest = match days {
+        0 => time_until(target_instant, now),
+        d => time_until(day_start(d).unwrap_or(target_instant), now)
+    };
+
+    Some((days, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{ NaiveDate, Utc };
+    use chrono_tz::{ America::Santiago, Europe::Budapest };
+
+    fn local(y: i32, m: u32, d: u32, h: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap().and_hms_opt(h, min, 0).unwrap()
+    }
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.from_utc_datetime(&local(y, m, d, h, min))
+    }
+
+    fn budapest(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<chrono_tz::Tz> {
+        Budapest.from_local_datetime(&local(y, m, d, h, min)).single().unwrap()
+    }
+
+    const HOUR: u64 = 3600;
+
+    #[test]
+    fn resolves_gap_and_overlap() {
+        // 02:00-03:00 is skipped on 2026-03-29 in Budapest
+        assert_eq!(resolve_local(&Budapest, local(2026, 3, 29, 2, 30)).unwrap(), utc(2026, 3, 29, 1, 0));
+        assert_eq!(resolve_local(&Budapest, local(2026, 3, 29, 1, 59)).unwrap(), utc(2026, 3, 29, 0, 59));
+        // 02:00-03:00 happens twice on 2026-10-25, the first one is in summer time
+        assert_eq!(resolve_local(&Budapest, local(2026, 10, 25, 2, 30)).unwrap(), utc(2026, 10, 25, 0, 30));
+        assert_eq!(resolve_local(&Budapest, local(2026, 10, 25, 3, 0)).unwrap(), utc(2026, 10, 25, 2, 0));
+    }
+
+    #[test]
+    fn next_midnight_across_dst() {
+        let midnight = next_midnight(budapest(2026, 3, 29, 12, 0));
+        assert_eq!(midnight, budapest(2026, 3, 30, 0, 0));
+        // The day of the change is 23 and 25 hours long
+        assert_eq!(time_until(midnight, budapest(2026, 3, 29, 0, 0)), Duration::from_secs(23 * HOUR));
+        let midnight = next_midnight(budapest(2026, 10, 25, 12, 0));
+        assert_eq!(time_until(midnight, budapest(2026, 10, 25, 0, 0)), Duration::from_secs(25 * HOUR));
+    }
+
+    #[test]
+    fn next_midnight_skipped() {
+        // Chile skips from 00:00 to 01:00 on 2026-09-06
+        let now = Santiago.from_local_datetime(&local(2026, 9, 5, 12, 0)).single().unwrap();
+        assert_eq!(next_midnight(now).naive_local(), local(2026, 9, 6, 1, 0));
+    }
+
+    #[test]
+    fn days_until_across_spring_forward() {
+        // Whole days on the calendar, even though it's 47 hours
+        assert_eq!(days_until(local(2026, 3, 30, 12, 0), budapest(2026, 3, 28, 12, 0)), Some((2, Duration::ZERO)));
+        // 18:00 to 12:00 the next day, with an hour skipped
+        assert_eq!(days_until(local(2026, 3, 30, 12, 0), budapest(2026, 3, 28, 18, 0)), Some((1, Duration::from_secs(17 * HOUR))));
+        // The target is in the gap
+        assert_eq!(days_until(local(2026, 3, 29, 2, 30), budapest(2026, 3, 29, 1, 0)), Some((0, Duration::from_secs(HOUR))));
+    }
+
+    #[test]
+    fn days_until_across_fall_back() {
+        assert_eq!(days_until(local(2026, 10, 26, 12, 0), budapest(2026, 10, 24, 12, 0)), Some((2, Duration::ZERO)));
+        assert_eq!(days_until(local(2026, 10, 26, 12, 0), budapest(2026, 10, 24, 18, 0)), Some((1, Duration::from_secs(19 * HOUR))));
+        // The target happens twice, counted to the first one
+        assert_eq!(days_until(local(2026, 10, 25, 2, 30), budapest(2026, 10, 25, 1, 30)), Some((0, Duration::from_secs(HOUR))));
+    }
+
+    #[test]
+    fn days_until_passed() {
+        assert_eq!(days_until(local(2026, 3, 29, 12, 0), budapest(2026, 3, 29, 12, 0)), None);
+        assert_eq!(days_until(local(2026, 3, 28, 12, 0), budapest(2026, 3, 29, 12, 0)), None);
+    }
+}
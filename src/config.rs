@@ -0,0 +1,629 @@
+use gtk::glib::{ self, KeyFile, KeyFileFlags };
+
+use chrono::{ NaiveDate, NaiveDateTime, NaiveTime, Weekday };
+use chrono::format::{ Item, StrftimeItems };
+use chrono_tz::Tz;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::clock::{ ClockFormat, HourFormat };
+use crate::digital_clock::ClockTransition;
+use crate::analog_clock::SecondHand;
+use crate::weather::Cordinates;
+use crate::calendar::caldav;
+
+// The config is an ini style file (parsed with glib's KeyFile) at $XDG_CONFIG_HOME/dinfo/config.ini
+// Every key is optional, missing keys fall back to the defaults.
+//
+// [theme]
+// dark=catppuccin-mocha
+// light=catppuccin-latte
+// wallpaper=~/.cache/wal/colors.json
+//
+// [clock]
+// # 24h, 12h, or a custom strftime pattern like %H.%M
+// format=24h
+// seconds=true
+// am-pm=true
+// # fade, slide-up, flip or none
+// transition=fade
+// # digital or analog
+// face=digital
+// # ticking, smooth or none
+// second-hand=ticking
+// # strftime pattern of the date under the clock, %V is the ISO week number, %j the day of the year
+// date-format=%d/%m/%Y
+//
+// [calendar]
+// # The first day of the week in the month view
+// week-start=monday
+// week-numbers=true
+// # .ics files or directories of them (like vdirsyncer's), separated by ;
+// ics=~/.local/share/vdirsyncer/calendars;~/Documents/work.ics
+//
+// # CalDAV calendars, synced to $XDG_CACHE_HOME/dinfo/caldav/<name>
+// [caldav:Team]
+// url=http://localhost:5232/user/team-calendar/
+// username=user
+// # The password, or a command that prints it
+// password-command=pass show radicale
+// # Minutes between syncs
+// interval=15
+//
+// [holidays]
+// # ISO 3166 country code, DE, GB, HU and US are bundled, others can be put in $XDG_CONFIG_HOME/dinfo/holidays/<country>.ini
+// country=DE
+// # Only needed for regional holidays, like the German states (BY) or the UK's countries (ENG).
+// # Without it they arent shown, like Easter Monday in the UK
+// region=BY
+// # A dataset file used instead of the country's
+// file=~/holidays.ini
+// # Show observances like Mother's Day too
+// observances=true
+// # Holidays in the date's tooltip
+// upcoming=5
+//
+// [timers]
+// # Played when a timer or an alarm goes off
+// sound=/usr/share/sounds/freedesktop/stereo/complete.oga
+//
+// [pomodoro]
+// enabled=true
+// # Lengths in minutes
+// work=25
+// short-break=5
+// long-break=15
+// # Work sessions before a long break
+// cycles=4
+//
+// [battery]
+// # Send a notification when a device's charge drops under low, and again under critical (percent)
+// notify=true
+// low=10
+// critical=5
+//
+// # Thresholds of a device kind (battery, mouse, keyboard, headset, headphones, ...), missing keys come from [battery]
+// [battery:headset]
+// low=20
+// # bluetooth is used for the devices connected through bluez that have no group of their kind
+// [battery:bluetooth]
+// low=15
+//
+// # Per monitor settings, by connector name
+// [monitor:DP-1]
+// clock=analog
+//
+// # World clocks, shown under the clock in the order they are in the file.
+// # zone is an IANA time zone, lat and lng are optional and used for the day/night marker
+// [world-clock:Tokyo]
+// zone=Asia/Tokyo
+// lat=35.6762
+// lng=139.6503
+//
+// # Countdowns to dates, shown under the date until they pass. time is optional, midnight by default
+// [countdown:Release freeze]
+// date=2026-12-01
+// time=17:00
+
+/// The directory dinfo reads user files from, $XDG_CONFIG_HOME/dinfo
+pub fn config_dir() -> PathBuf {
+    glib::user_config_dir().join("dinfo")
+}
+
+/// Expands a leading ~ to the home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => glib::home_dir().join(rest),
+        None => PathBuf::from(path)
+    }
+}
+
+/// Path of the config file, $XDG_CONFIG_HOME/dinfo/config.ini
+pub fn config_path() -> PathBuf {
+    config_dir().join("config.ini")
+}
+
+#[derive(Debug, Clone)]
+pub struct ThemeConfig {
+    /// Name of the palette used when the desktop prefers dark (or has no preference)
+    pub dark: String,
+    /// Name of the palette used when the desktop prefers light
+    pub light: String,
+    /// Pywal or matugen color file to take the colors from, None if it's disabled (set to an empty string)
+    pub wallpaper: Option<String>
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        ThemeConfig {
+            dark: "catppuccin-mocha".to_string(),
+            light: "catppuccin-latte".to_string(),
+            wallpaper: Some("~/.cache/wal/colors.json".to_string())
+        }
+    }
+}
+
+/// Which clock is shown
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClockFace {
+    Digital,
+    Analog
+}
+
+impl ClockFace {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "digital" => Some(Self::Digital),
+            "analog" => Some(Self::Analog),
+            _ => None
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ClockConfig {
+    pub format: ClockFormat,
+    /// How the digits change
+    pub transition: ClockTransition,
+    /// The clock used on monitors without their own setting
+    pub face: ClockFace,
+    /// How the analog clock's second hand moves
+    pub second_hand: SecondHand,
+    /// The date under the clock, a strftime pattern
+    pub date_format: String
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        ClockConfig {
+            format: ClockFormat::default(),
+            transition: ClockTransition::Fade,
+            face: ClockFace::Digital,
+            second_hand: SecondHand::Ticking,
+            date_format: "%d/%m/%Y".to_string()
+        }
+    }
+}
+
+/// A calendar on a CalDAV server
+#[derive(Debug, Clone)]
+pub struct CalDavConfig {
+    /// The name of the group, also used for the cache directory
+    pub name: String,
+    /// The calendar collection, like http://localhost:5232/user/calendar/
+    pub url: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// A shell command that prints the password, used instead of password if it's set
+    pub password_command: Option<String>,
+    /// Time between syncs
+    pub interval: Duration
+}
+
+#[derive(Debug, Clone)]
+pub struct CalendarConfig {
+    /// The first column of the month view
+    pub week_start: Weekday,
+    /// Show the ISO week numbers in front of the weeks
+    pub week_numbers: bool,
+    /// .ics files and directories the agenda reads
+    pub ics: Vec<PathBuf>,
+    pub caldav: Vec<CalDavConfig>
+}
+
+impl CalendarConfig {
+    /// Every path the agenda reads, the CalDAV calendars are read from their cache.
+    /// No agenda is shown if it's empty
+    pub fn agenda_paths(&self) -> Vec<PathBuf> {
+        self.ics
+            .iter()
+            .cloned()
+            .chain(self.caldav.iter().map(|c| caldav::cache_dir(&c.name)))
+            .collect()
+    }
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        CalendarConfig { week_start: Weekday::Mon, week_numbers: true, ics: Vec::new(), caldav: Vec::new() }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HolidaysConfig {
+    /// ISO 3166 country code, no holidays are shown without it
+    pub country: Option<String>,
+    pub region: Option<String>,
+    /// Used instead of the country's dataset
+    pub file: Option<PathBuf>,
+    pub observances: bool,
+    /// How many upcoming holidays are in the date's tooltip
+    pub upcoming: usize
+}
+
+impl Default for HolidaysConfig {
+    fn default() -> Self {
+        HolidaysConfig { country: None, region: None, file: None, observances: true, upcoming: 5 }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TimersConfig {
+    /// Played when a timer or an alarm (without its own sound) goes off
+    pub sound: Option<PathBuf>
+}
+
+#[derive(Debug, Clone)]
+pub struct PomodoroConfig {
+    /// Show the pomodoro ring under the clock
+    pub enabled: bool,
+    pub work: Duration,
+    pub short_break: Duration,
+    pub long_break: Duration,
+    /// Work sessions before a long break
+    pub cycles: u32
+}
+
+impl Default for PomodoroConfig {
+    fn default() -> Self {
+        PomodoroConfig {
+            enabled: false,
+            work: Duration::from_secs(25 * 60),
+            short_break: Duration::from_secs(5 * 60),
+            long_break: Duration::from_secs(15 * 60),
+            cycles: 4
+        }
+    }
+}
+
+/// When a low battery notification is sent, in percent
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BatteryThresholds {
+    pub low: f64,
+    pub critical: f64
+}
+
+#[derive(Debug, Clone)]
+pub struct BatteryConfig {
+    pub notify: bool,
+    pub thresholds: BatteryThresholds,
+    /// By device kind, like headset (see upower's type names), and bluetooth
+    pub kinds: HashMap<String, BatteryThresholds>
+}
+
+impl Default for BatteryConfig {
+    fn default() -> Self {
+        BatteryConfig { notify: true, thresholds: BatteryThresholds { low: 10.0, critical: 5.0 }, kinds: HashMap::new() }
+    }
+}
+
+impl BatteryConfig {
+    /// The thresholds of a device kind, bluetooth devices fall back to the bluetooth group before the defaults
+    pub fn thresholds_for(&self, kind: &str, bluetooth: bool) -> BatteryThresholds {
+        self.kinds.get(kind)
+            .or_else(|| bluetooth.then(|| self.kinds.get("bluetooth")).flatten())
+            .copied()
+            .unwrap_or(self.thresholds)
+    }
+}
+
+/// Settings of a single monitor, None values fall back to the global ones
+#[derive(Debug, Clone, Default)]
+pub struct MonitorConfig {
+    pub clock_face: Option<ClockFace>
+}
+
+/// A clock showing the time in another time zone
+#[derive(Debug, Clone)]
+pub struct WorldClockConfig {
+    /// The label shown above the time, the name of the group
+    pub name: String,
+    pub zone: Tz,
+    /// Used to tell if it's day or night there, if missing it's guessed from the hour
+    pub location: Option<Cordinates>
+}
+
+/// A named date counted down to under the clock
+#[derive(Debug, Clone)]
+pub struct CountdownConfig {
+    /// The name of the group
+    pub name: String,
+    /// On the local wall clock
+    pub target: NaiveDateTime
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    pub theme: ThemeConfig,
+    pub clock: ClockConfig,
+    pub calendar: CalendarConfig,
+    pub holidays: HolidaysConfig,
+    pub timers: TimersConfig,
+    pub pomodoro: PomodoroConfig,
+    pub battery: BatteryConfig,
+    /// Monitor settings by connector name (like DP-1)
+    pub monitors: HashMap<String, MonitorConfig>,
+    pub world_clocks: Vec<WorldClockConfig>,
+    pub countdowns: Vec<CountdownConfig>
+}
+
+impl Config {
+    /// Loads the config file, if it doesnt exist or it's invalid the defaults are used
+    pub fn load() -> Self {
+        let path = config_path();
+        if !path.exists() {
+            return Config::default();
+        }
+
+        let file = KeyFile::new();
+        if let Err(e) = file.load_from_file(&path, KeyFileFlags::NONE) {
+            eprintln!("Failed to load config from {}, using the defaults: {e}", path.display());
+            return Config::default();
+        }
+
+        Self::from_key_file(&file)
+    }
+
+    pub fn from_key_file(file: &KeyFile) -> Self {
+        let default = Config::default();
+
+        let theme = ThemeConfig {
+            dark: string(file, "theme", "dark").unwrap_or(default.theme.dark),
+            light: string(file, "theme", "light").unwrap_or(default.theme.light),
+            wallpaper: match string(file, "theme", "wallpaper") {
+                Some(path) if path.is_empty() => None,
+                Some(path) => Some(path),
+                None => default.theme.wallpaper
+            }
+        };
+
+        let format = match string(file, "clock", "format").as_deref() {
+            None | Some("24h") => ClockFormat {
+                hours: HourFormat::H24,
+                seconds: boolean(file, "clock", "seconds").unwrap_or(default.clock.format.seconds),
+                am_pm: false,
+                custom: None
+            },
+            Some("12h") => ClockFormat {
+                hours: HourFormat::H12,
+                seconds: boolean(file, "clock", "seconds").unwrap_or(default.clock.format.seconds),
+                am_pm: boolean(file, "clock", "am-pm").unwrap_or(default.clock.format.am_pm),
+                custom: None
+            },
+            Some(custom) if is_valid_strftime(custom) => ClockFormat {
+                custom: Some(custom.to_string()),
+                ..default.clock.format
+            },
+            Some(custom) => {
+                eprintln!("Invalid clock format '{custom}' in config, using the default");
+                default.clock.format
+            }
+        };
+        let transition = match string(file, "clock", "transition") {
+            Some(name) => ClockTransition::from_name(&name).unwrap_or_else(|| {
+                eprintln!("Unknown clock transition '{name}' in config, using the default. Available: fade, slide-up, flip, none");
+                default.clock.transition
+            }),
+            None => default.clock.transition
+        };
+
+        let face = match string(file, "clock", "face") {
+            Some(name) => ClockFace::from_name(&name).unwrap_or_else(|| {
+                eprintln!("Unknown clock face '{name}' in config, using the default. Available: digital, analog");
+                default.clock.face
+            }),
+            None => default.clock.face
+        };
+
+        let second_hand = match string(file, "clock", "second-hand") {
+            Some(name) => SecondHand::from_name(&name).unwrap_or_else(|| {
+                eprintln!("Unknown second hand '{name}' in config, using the default. Available: ticking, smooth, none");
+                default.clock.second_hand
+            }),
+            None => default.clock.second_hand
+        };
+
+        let date_format = match string(file, "clock", "date-format") {
+            Some(pattern) if is_valid_strftime(&pattern) => pattern,
+            Some(pattern) => {
+                eprintln!("Invalid date format '{pattern}' in config, using the default");
+                default.clock.date_format
+            },
+            None => default.clock.date_format
+        };
+
+        let clock = ClockConfig { format, transition, face, second_hand, date_format };
+
+        let week_start = match string(file, "calendar", "week-start") {
+            Some(name) => name.parse::<Weekday>().unwrap_or_else(|_| {
+                eprintln!("Unknown week start '{name}' in config, using the default. Use the name of a day, like monday");
+                default.calendar.week_start
+            }),
+            None => default.calendar.week_start
+        };
+
+        let mut calendar = CalendarConfig {
+            week_start,
+            week_numbers: boolean(file, "calendar", "week-numbers").unwrap_or(default.calendar.week_numbers),
+            ics: match file.string_list("calendar", "ics") {
+                Ok(paths) => paths.iter().filter(|p| !p.is_empty()).map(|p| expand_home(p)).collect(),
+                Err(_) => default.calendar.ics
+            },
+            caldav: Vec::new()
+        };
+
+        let holidays = HolidaysConfig {
+            country: string(file, "holidays", "country").filter(|s| !s.is_empty()),
+            region: string(file, "holidays", "region").filter(|s| !s.is_empty()),
+            file: string(file, "holidays", "file").filter(|s| !s.is_empty()).map(|s| expand_home(&s)),
+            observances: boolean(file, "holidays", "observances").unwrap_or(default.holidays.observances),
+            upcoming: file.uint64("holidays", "upcoming").map(|n| n as usize).unwrap_or(default.holidays.upcoming)
+        };
+
+        let timers = TimersConfig {
+            sound: string(file, "timers", "sound").filter(|s| !s.is_empty()).map(|s| expand_home(&s))
+        };
+
+        let minutes = |key: &str, default: Duration| {
+            file.uint64("pomodoro", key).map(|m| Duration::from_secs(m.max(1) * 60)).unwrap_or(default)
+        };
+        let pomodoro = PomodoroConfig {
+            enabled: boolean(file, "pomodoro", "enabled").unwrap_or(default.pomodoro.enabled),
+            work: minutes("work", default.pomodoro.work),
+            short_break: minutes("short-break", default.pomodoro.short_break),
+            long_break: minutes("long-break", default.pomodoro.long_break),
+            cycles: file.uint64("pomodoro", "cycles").map(|c| c.clamp(1, 12) as u32).unwrap_or(default.pomodoro.cycles)
+        };
+
+        let percent = |group: &str, key: &str, default: f64| {
+            double(file, group, key).map(|p| p.clamp(0.0, 100.0)).unwrap_or(default)
+        };
+        let mut battery = BatteryConfig {
+            notify: boolean(file, "battery", "notify").unwrap_or(default.battery.notify),
+            thresholds: BatteryThresholds {
+                low: percent("battery", "low", default.battery.thresholds.low),
+                critical: percent("battery", "critical", default.battery.thresholds.critical)
+            },
+            kinds: HashMap::new()
+        };
+
+        let mut monitors = HashMap::new();
+        let mut world_clocks = Vec::new();
+        let mut countdowns = Vec::new();
+        for group in file.groups().iter() {
+            if let Some(name) = group.to_gstr().as_str().strip_prefix("world-clock:") {
+                if let Some(world_clock) = world_clock(file, group.to_gstr().as_str(), name) {
+                    world_clocks.push(world_clock);
+                }
+                continue;
+            }
+
+            if let Some(name) = group.to_gstr().as_str().strip_prefix("countdown:") {
+                if let Some(countdown) = countdown(file, group.to_gstr().as_str(), name) {
+                    countdowns.push(countdown);
+                }
+                continue;
+            }
+
+            if let Some(kind) = group.to_gstr().as_str().strip_prefix("battery:") {
+                let thresholds = BatteryThresholds {
+                    low: percent(group.to_gstr().as_str(), "low", battery.thresholds.low),
+                    critical: percent(group.to_gstr().as_str(), "critical", battery.thresholds.critical)
+                };
+                battery.kinds.insert(kind.to_lowercase(), thresholds);
+                continue;
+            }
+
+            if let Some(name) = group.to_gstr().as_str().strip_prefix("caldav:") {
+                match string(file, group.to_gstr().as_str(), "url") {
+                    Some(url) => calendar.caldav.push(CalDavConfig {
+                        name: name.to_string(),
+                        url,
+                        username: string(file, group.to_gstr().as_str(), "username"),
+                        password: string(file, group.to_gstr().as_str(), "password"),
+                        password_command: string(file, group.to_gstr().as_str(), "password-command"),
+                        interval: Duration::from_secs(
+                            file.uint64(group.to_gstr().as_str(), "interval").unwrap_or(15).max(1) * 60
+                        )
+                    }),
+                    None => eprintln!("CalDAV calendar '{name}' has no url in config, skipping it")
+                }
+                continue;
+            }
+
+            let Some(connector) = group.to_gstr().as_str().strip_prefix("monitor:") else {
+                continue;
+            };
+
+            let clock_face = string(file, group.to_gstr().as_str(), "clock").and_then(|name| {
+                let face = ClockFace::from_name(&name);
+                if face.is_none() {
+                    eprintln!("Unknown clock face '{name}' for monitor {connector} in config. Available: digital, analog");
+                }
+                face
+            });
+
+            monitors.insert(connector.to_string(), MonitorConfig { clock_face });
+        }
+
+        Config { theme, clock, calendar, holidays, timers, pomodoro, battery, monitors, world_clocks, countdowns }
+    }
+
+    /// The clock face to use on a monitor
+    pub fn clock_face_for(&self, connector: Option<&str>) -> ClockFace {
+        connector
+            .and_then(|c| self.monitors.get(c))
+            .and_then(|m| m.clock_face)
+            .unwrap_or(self.clock.face)
+    }
+}
+
+/// Reads a string from the key file, None if the group or the key is missing
+pub fn string(file: &KeyFile, group: &str, key: &str) -> Option<String> {
+    file.string(group, key).ok().map(|s| s.to_string())
+}
+
+/// Reads a boolean from the key file, None if it's missing or not a boolean
+pub fn boolean(file: &KeyFile, group: &str, key: &str) -> Option<bool> {
+    file.boolean(group, key).ok()
+}
+
+/// Reads a number from the key file, None if it's missing or not a number
+pub fn double(file: &KeyFile, group: &str, key: &str) -> Option<f64> {
+    file.double(group, key).ok()
+}
+
+/// Reads a [world-clock:<name>] group, None if the zone is missing or unknown
+fn world_clock(file: &KeyFile, group: &str, name: &str) -> Option<WorldClockConfig> {
+    let Some(zone_name) = string(file, group, "zone") else {
+        eprintln!("World clock '{name}' has no zone in config, skipping it");
+        return None;
+    };
+
+    let zone = match zone_name.parse::<Tz>() {
+        Ok(zone) => zone,
+        Err(_) => {
+            eprintln!("Unknown time zone '{zone_name}' for world clock '{name}' in config, skipping it");
+            return None;
+        }
+    };
+
+    let location = match (double(file, group, "lat"), double(file, group, "lng")) {
+        (Some(lat), Some(lng)) => Some(Cordinates::new(lat, lng)),
+        _ => None
+    };
+
+    Some(WorldClockConfig { name: name.to_string(), zone, location })
+}
+
+/// Reads a [countdown:<name>] group, None if the date or the time is missing or invalid
+fn countdown(file: &KeyFile, group: &str, name: &str) -> Option<CountdownConfig> {
+    let Some(date) = string(file, group, "date") else {
+        eprintln!("Countdown '{name}' has no date in config, skipping it");
+        return None;
+    };
+
+    let Ok(date) = NaiveDate::parse_from_str(&date, "%Y-%m-%d") else {
+        eprintln!("Invalid date '{date}' for countdown '{name}' in config, skipping it. Use a date like 2025-12-24");
+        return None;
+    };
+
+    let time = match string(file, group, "time") {
+        Some(time) => match crate::timers::alarms::parse_time(&time) {
+            Some(time) => time,
+            None => {
+                eprintln!("Invalid time '{time}' for countdown '{name}' in config, skipping it. Use a time like 17:00");
+                return None;
+            }
+        },
+        None => NaiveTime::MIN
+    };
+
+    Some(CountdownConfig { name: name.to_string(), target: date.and_time(time) })
+}
+
+/// Checks a strftime pattern, chrono panics when formatting with an invalid one
+pub fn is_valid_strftime(pattern: &str) -> bool {
+    StrftimeItems::new(pattern).all(|item| item != Item::Error)
+}
@@ -0,0 +1,26 @@
+use std::cell::RefCell;
+
+use gtk::{ glib, Label, Overlay };
+use gtk::subclass::prelude::*;
+
+/// The 2 layers of a digit, cross faded into each other
+pub struct DigitCell {
+    pub cell: Overlay,
+    pub layers: [Label; 2]
+}
+
+#[derive(Default)]
+pub struct DigitalClock {
+    pub cells: RefCell<Vec<DigitCell>>
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for DigitalClock {
+    const NAME: &'static str = "DigitalClock";
+    type Type = super::DigitalClock;
+    type ParentType = gtk::Box;
+}
+
+impl ObjectImpl for DigitalClock {}
+impl WidgetImpl for DigitalClock {}
+impl BoxImpl for DigitalClock {}
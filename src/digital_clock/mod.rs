@@ -0,0 +1,147 @@
+use glib::Object;
+use gtk::{ Align, Label, Overlay, glib, prelude::*, subclass::prelude::* };
+
+use std::time::Duration;
+
+use crate::clock::Clock;
+
+mod imp;
+use imp::DigitCell;
+
+// A clock made of one cell per character, every cell has 2 labels (layers) on top of each other.
+// The Clock state tells which layer to show, and the transition between them is done in css,
+// picked with a class on the widget: .clock-fade, .clock-slide-up, .clock-flip or .clock-none
+
+/// How the digits change
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClockTransition {
+    Fade,
+    SlideUp,
+    Flip,
+    None
+}
+
+impl ClockTransition {
+    /// Parses the name used in the config: fade, slide-up, flip or none
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "fade" => Some(Self::Fade),
+            "slide-up" => Some(Self::SlideUp),
+            "flip" => Some(Self::Flip),
+            "none" => Some(Self::None),
+            _ => None
+        }
+    }
+
+    pub fn css_class(&self) -> &'static str {
+        match self {
+            Self::Fade => "clock-fade",
+            Self::SlideUp => "clock-slide-up",
+            Self::Flip => "clock-flip",
+            Self::None => "clock-none"
+        }
+    }
+}
+
+glib::wrapper!{
+    pub struct DigitalClock(ObjectSubclass<imp::DigitalClock>)
+        @extends gtk::Box, gtk::Widget,
+        @implements gtk::Accessible, gtk::Buildable, gtk::ConstraintTarget, gtk::Orientable;
+}
+
+impl DigitalClock {
+    pub fn new(clock_state: &Clock, transition: ClockTransition) -> Self {
+        let obj: DigitalClock = Object::builder()
+            .property("orientation", gtk::Orientation::Horizontal)
+            .property("halign", Align::Center)
+            .property("valign", Align::Center)
+            .build();
+
+        obj.add_css_class(transition.css_class());
+        obj.set_digit_count(clock_state.digit_count());
+
+        clock_state.connect_digit_count_notify(glib::clone!(
+            #[weak]
+            obj,
+            move |state| obj.set_digit_count(state.digit_count())
+        ));
+
+        clock_state.connect_closure("digit-label", false, glib::closure_local!(
+            #[weak]
+            obj,
+            move |_obj: &Clock, index: u32, layer: u32, label: String| -> () {
+                if let Some(layer) = obj.layer(index, layer) {
+                    layer.set_label(&label);
+                }
+            }
+        ));
+
+        clock_state.connect_closure("digit-should-fade", false, glib::closure_local!(
+            #[weak]
+            obj,
+            move |_obj: &Clock, index: u32, layer: u32, new_state: bool| -> () {
+                let Some(layer) = obj.layer(index, layer) else {
+                    return;
+                };
+
+                if new_state {
+                    layer.add_css_class("fade-out");
+                } else {
+                    // Jump to the starting position of the transition first (below for slide-up),
+                    // then let the css transition move it into place
+                    layer.add_css_class("enter");
+                    layer.remove_css_class("fade-out");
+                    glib::timeout_add_local_once(Duration::from_millis(20), glib::clone!(
+                        #[weak]
+                        layer,
+                        move || layer.remove_css_class("enter")
+                    ));
+                }
+            }
+        ));
+
+        obj
+    }
+
+    /// Returns a layer (1 or 2) of a digit
+    fn layer(&self, index: u32, layer: u32) -> Option<Label> {
+        let cells = self.imp().cells.borrow();
+        let cell = cells.get(index as usize)?;
+        cell.layers.get(layer.checked_sub(1)? as usize).cloned()
+    }
+
+    /// Rebuilds the cells, every layer starts out hidden
+    fn set_digit_count(&self, count: u32) {
+        let mut cells = self.imp().cells.borrow_mut();
+
+        for cell in cells.drain(..) {
+            self.remove(&cell.cell);
+        }
+
+        for _ in 0..count {
+            let new_layer = || {
+                Label::builder()
+                .css_classes(["text", "clock", "digit", "fade-out"])
+                .vexpand(false)
+                .halign(Align::Center)
+                .valign(Align::Center)
+                .build()
+            };
+            let layers = [new_layer(), new_layer()];
+
+            let cell = {
+                Overlay::builder()
+                .halign(Align::Center)
+                .valign(Align::Center)
+                .child(&layers[0])
+                .build()
+            };
+            cell.add_overlay(&layers[1]);
+            // Size the cell by the wider of the two, they can hold different characters (like AM and PM)
+            cell.set_measure_overlay(&layers[1], true);
+
+            self.append(&cell);
+            cells.push(DigitCell { cell, layers });
+        }
+    }
+}
@@ -7,6 +7,10 @@ use gtk::subclass::prelude::*;
 
 use crate::clock::Clock;
 use crate::weather_state::WeatherState;
+use crate::agenda_state::AgendaState;
+use crate::timers::Timers;
+use crate::pomodoro::Pomodoro;
+use crate::upower::UPower;
 
 #[derive(Default, Properties)]
 #[properties[wrapper_type = super::GlobalState]]
@@ -14,7 +18,15 @@ pub struct GlobalState {
     #[property(get, set)]
     clock: RefCell<Option<Clock>>,
     #[property(get, set)]
-    weather: RefCell<Option<WeatherState>>
+    weather: RefCell<Option<WeatherState>>,
+    #[property(get, set)]
+    agenda: RefCell<Option<AgendaState>>,
+    #[property(get, set)]
+    timers: RefCell<Option<Timers>>,
+    #[property(get, set)]
+    pomodoro: RefCell<Option<Pomodoro>>,
+    #[property(get, set)]
+    upower: RefCell<Option<UPower>>
 }
 
 #[glib::object_subclass]
@@ -5,6 +5,10 @@ mod imp;
 
 use crate::clock::Clock;
 use crate::weather_state::WeatherState;
+use crate::agenda_state::AgendaState;
+use crate::timers::Timers;
+use crate::pomodoro::Pomodoro;
+use crate::upower::UPower;
 
 glib::wrapper!{
     pub struct GlobalState(ObjectSubclass<imp::GlobalState>);
@@ -15,6 +19,10 @@ impl GlobalState {
         let obj = Object::builder()
         .property("clock", Some(Clock::new()))
         .property("weather", Some(WeatherState::new()))
+        .property("agenda", Some(AgendaState::new()))
+        .property("timers", Some(Timers::new()))
+        .property("pomodoro", Some(Pomodoro::new()))
+        .property("upower", Some(UPower::new()))
         .build();
 
         glib::spawn_future_local(async move {
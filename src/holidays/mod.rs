@@ -0,0 +1,285 @@
+use gtk::glib::{ KeyFile, KeyFileFlags };
+use chrono::{ Datelike, NaiveDate };
+
+use std::path::PathBuf;
+
+mod rules;
+pub use rules::{ Rule, Substitute };
+
+use crate::config::{ HolidaysConfig, config_dir };
+
+// Holidays are read from an ini file per country, every group is a holiday:
+//
+// [whit-monday]
+// # The name shown, the group's name if it's missing
+// name=Whit Monday
+// easter=50
+// # Only in these regions (like German states), everywhere if it's missing
+// regions=BW;BY
+// # Observances arent days off, like Mother's Day
+// observance=false
+// # Another day off when it's on a weekend
+// substitute=next-weekday
+//
+// See rules.rs for the rules. The file is looked up in this order:
+// the config's file key, $XDG_CONFIG_HOME/dinfo/holidays/<country>.ini, then the bundled ones.
+
+/// The bundled datasets, by ISO 3166 country code
+const BUNDLED: &[(&str, &str)] = &[
+    ("DE", include_str!("data/de.ini")),
+    ("GB", include_str!("data/gb.ini")),
+    ("HU", include_str!("data/hu.ini")),
+    ("US", include_str!("data/us.ini")),
+];
+
+#[derive(Debug, Clone)]
+pub struct Holiday {
+    pub name: String,
+    pub rule: Rule,
+    pub observance: bool,
+    pub substitute: Option<Substitute>
+}
+
+/// A holiday on a date, or its substitute day
+#[derive(Debug, Clone, Copy)]
+pub struct HolidayDate<'a> {
+    pub date: NaiveDate,
+    pub holiday: &'a Holiday,
+    pub substitute: bool
+}
+
+impl HolidayDate<'_> {
+    /// The holiday's name, like "Boxing Day (substitute day)" on a substitute day
+    pub fn name(&self) -> String {
+        match (self.substitute, self.holiday.substitute) {
+            (true, Some(substitute)) => format!("{} ({})", self.holiday.name, substitute.label()),
+            _ => self.holiday.name.clone()
+        }
+    }
+}
+
+/// The holidays of the configured country and region
+#[derive(Debug, Clone, Default)]
+pub struct Holidays {
+    holidays: Vec<Holiday>
+}
+
+impl Holidays {
+    /// Loads the holidays of the configured country, empty if there is no country configured or it has no dataset
+    pub fn load(config: &HolidaysConfig) -> Self {
+        let Some(country) = &config.country else {
+            return Holidays::default();
+        };
+
+        let file = KeyFile::new();
+        let user_file = config.file.clone().unwrap_or_else(|| user_dataset_path(country));
+
+        let loaded = match user_file.exists() {
+            true => file.load_from_file(&user_file, KeyFileFlags::NONE).map_err(|e| {
+                eprintln!("Failed to load holidays from {}: {e}", user_file.display());
+            }),
+            false => match BUNDLED.iter().find(|(code, _)| code.eq_ignore_ascii_case(country)) {
+                Some((_, data)) => file.load_from_data(data, KeyFileFlags::NONE).map_err(|e| {
+                    eprintln!("Failed to load the bundled holidays of {country}: {e}");
+                }),
+                None => {
+                    eprintln!(
+                        "No holidays for country '{country}', put them in {} or use one of: {}",
+                        user_file.display(),
+                        BUNDLED.iter().map(|(code, _)| *code).collect::<Vec<_>>().join(", ")
+                    );
+                    Err(())
+                }
+            }
+        };
+
+        if loaded.is_err() {
+            return Holidays::default();
+        }
+
+        Self::from_key_file(&file, config)
+    }
+
+    pub fn from_key_file(file: &KeyFile, config: &HolidaysConfig) -> Self {
+        let mut holidays = Vec::new();
+
+        for group in file.groups().iter() {
+            let group = group.to_gstr().as_str();
+
+            // Regional holidays only count in their regions
+            if let Ok(regions) = file.string_list(group, "regions") {
+                let in_region = config.region.as_ref()
+                    .is_some_and(|region| regions.iter().any(|r| r.eq_ignore_ascii_case(region)));
+                if !in_region {
+                    continue;
+                }
+            }
+
+            let observance = file.boolean(group, "observance").unwrap_or(false);
+            if observance && !config.observances {
+                continue;
+            }
+
+            let rule = ["date", "easter", "orthodox-easter", "weekday", "on-or-after"]
+                .iter()
+                .find_map(|key| file.string(group, key).ok().map(|value| (key, value)));
+
+            let Some((key, value)) = rule else {
+                eprintln!("Holiday '{group}' has no date, skipping it");
+                continue;
+            };
+
+            let Some(rule) = Rule::parse(key, value.as_str()) else {
+                eprintln!("Invalid {key} '{value}' for holiday '{group}', skipping it");
+                continue;
+            };
+
+            let substitute = file.string(group, "substitute").ok().and_then(|value| {
+                let substitute = Substitute::parse(value.as_str());
+                if substitute.is_none() {
+                    eprintln!("Invalid substitute '{value}' for holiday '{group}', ignoring it");
+                }
+                substitute
+            });
+
+            holidays.push(Holiday {
+                name: file.string(group, "name").map(|n| n.to_string()).unwrap_or_else(|_| group.to_string()),
+                rule,
+                observance,
+                substitute
+            });
+        }
+
+        Holidays { holidays }
+    }
+
+    /// The holidays of a year and their substitute days, by date.
+    /// A substitute day can be in the previous or the next year, like New Year's Day observed on December 31
+    fn dates_in(&self, year: i32) -> Vec<HolidayDate<'_>> {
+        let mut dates: Vec<HolidayDate> = self.holidays
+            .iter()
+            .filter_map(|holiday| Some(HolidayDate { date: holiday.rule.date_in(year)?, holiday, substitute: false }))
+            .collect();
+        dates.sort_by_key(|d| d.date);
+
+        // Substitutes are found in the order of the holidays, so they dont end up on the same day
+        let mut days_off: Vec<NaiveDate> = dates.iter().filter(|d| !d.holiday.observance).map(|d| d.date).collect();
+        let mut substitutes = Vec::new();
+        for day in &dates {
+            let Some(date) = day.holiday.substitute.and_then(|s| s.date_for(day.date, |d| days_off.contains(&d))) else {
+                continue;
+            };
+            days_off.push(date);
+            substitutes.push(HolidayDate { date, holiday: day.holiday, substitute: true });
+        }
+
+        dates.extend(substitutes);
+        dates.sort_by_key(|d| d.date);
+        dates
+    }
+
+    /// The holidays on a day, with the ones whose substitute day it is
+    pub fn on(&self, date: NaiveDate) -> Vec<HolidayDate<'_>> {
+        (date.year() - 1..=date.year() + 1)
+            .flat_map(|year| self.dates_in(year))
+            .filter(|d| d.date == date)
+            .collect()
+    }
+
+    /// The next `count` holidays (and substitute days) after a day, within a year
+    pub fn upcoming(&self, after: NaiveDate, count: usize) -> Vec<HolidayDate<'_>> {
+        let mut dates: Vec<HolidayDate> = [after.year(), after.year() + 1]
+            .into_iter()
+            .flat_map(|year| self.dates_in(year))
+            .filter(|d| d.date > after)
+            .collect();
+        dates.sort_by_key(|d| d.date);
+
+        // Only the next one of every holiday
+        let mut upcoming: Vec<HolidayDate> = Vec::new();
+        for day in dates {
+            if !upcoming.iter().any(|u| std::ptr::eq(u.holiday, day.holiday) && u.substitute == day.substitute) {
+                upcoming.push(day);
+            }
+        }
+
+        upcoming.truncate(count);
+        upcoming
+    }
+}
+
+/// Where a user supplied dataset of a country is looked for
+fn user_dataset_path(country: &str) -> PathBuf {
+    config_dir().join("holidays").join(format!("{}.ini", country.to_lowercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holiday(name: &str, key: &str, value: &str, substitute: Option<Substitute>) -> Holiday {
+        Holiday { name: name.to_string(), rule: Rule::parse(key, value).unwrap(), observance: false, substitute }
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn names(holidays: Vec<HolidayDate>) -> Vec<String> {
+        holidays.iter().map(|h| h.name()).collect()
+    }
+
+    fn scotland() -> Holidays {
+        let substitute = Some(Substitute::NextWeekday);
+        Holidays { holidays: vec![
+            holiday("New Year's Day", "date", "01-01", substitute),
+            holiday("2nd January", "date", "01-02", substitute),
+            holiday("Christmas Day", "date", "12-25", substitute),
+            holiday("Boxing Day", "date", "12-26", substitute),
+        ] }
+    }
+
+    #[test]
+    fn substitute_days_dont_overlap() {
+        let holidays = scotland();
+
+        // Both on the weekend in 2027
+        assert_eq!(names(holidays.on(date(2027, 12, 25))), ["Christmas Day"]);
+        assert_eq!(names(holidays.on(date(2027, 12, 27))), ["Christmas Day (substitute day)"]);
+        assert_eq!(names(holidays.on(date(2027, 12, 28))), ["Boxing Day (substitute day)"]);
+
+        // Christmas is on a sunday in 2022, boxing day is a day off already
+        assert_eq!(names(holidays.on(date(2022, 12, 26))), ["Boxing Day"]);
+        assert_eq!(names(holidays.on(date(2022, 12, 27))), ["Christmas Day (substitute day)"]);
+
+        // 2022-01-01 is a saturday
+        assert_eq!(names(holidays.on(date(2022, 1, 3))), ["New Year's Day (substitute day)"]);
+        assert_eq!(names(holidays.on(date(2022, 1, 4))), ["2nd January (substitute day)"]);
+        assert!(holidays.on(date(2022, 1, 5)).is_empty());
+    }
+
+    #[test]
+    fn observed_in_the_previous_year() {
+        let holidays = Holidays { holidays: vec![
+            holiday("New Year's Day", "date", "01-01", Some(Substitute::NearestWeekday)),
+            holiday("Independence Day", "date", "07-04", Some(Substitute::NearestWeekday)),
+        ] };
+
+        // 2022-01-01 is a saturday
+        assert_eq!(names(holidays.on(date(2021, 12, 31))), ["New Year's Day (observed)"]);
+        assert_eq!(names(holidays.on(date(2026, 7, 3))), ["Independence Day (observed)"]);
+
+        let upcoming = holidays.upcoming(date(2021, 12, 1), 3);
+        assert_eq!(upcoming.iter().map(|h| h.date).collect::<Vec<_>>(), [date(2021, 12, 31), date(2022, 1, 1), date(2022, 7, 4)]);
+    }
+
+    #[test]
+    fn upcoming_only_the_next_one() {
+        let holidays = scotland();
+        assert_eq!(names(holidays.upcoming(date(2026, 12, 1), 10)), [
+            "Christmas Day", "Boxing Day", "Boxing Day (substitute day)", "New Year's Day", "2nd January", "2nd January (substitute day)",
+            // Christmas isnt on a weekend until 2027
+            "Christmas Day (substitute day)"
+        ]);
+    }
+}
@@ -0,0 +1,249 @@
+use chrono::{ Datelike, Days, Month, NaiveDate, Weekday };
+
+// How the date of a holiday is found in a year. In the dataset files a holiday has one of these keys:
+//
+// date=12-25                   Every year on the same day
+// easter=-2                    Days after (or before) Easter Sunday
+// orthodox-easter=1            Days after (or before) the Orthodox Easter Sunday
+// weekday=4 thursday november  The nth weekday of a month, or "last" like: last monday may
+// on-or-after=11-16 wednesday  The first weekday on or after a day, like Midsummer Day or the Day of Repentance
+//
+// A holiday falling on a weekend can have another day off, with the substitute key:
+//
+// substitute=next-weekday      The first weekday after it that isnt a holiday already, like the UK's substitute days
+// substitute=nearest-weekday   The friday before it if it's on a saturday, the monday after if it's on a sunday (US)
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rule {
+    Fixed { month: u32, day: u32 },
+    Easter(i64),
+    OrthodoxEaster(i64),
+    /// n is 1-5, or -1 for the last one in the month
+    NthWeekday { month: u32, weekday: Weekday, n: i8 },
+    OnOrAfter { month: u32, day: u32, weekday: Weekday }
+}
+
+/// The day off instead of a holiday that falls on a weekend
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Substitute {
+    NextWeekday,
+    NearestWeekday
+}
+
+impl Substitute {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "next-weekday" => Some(Substitute::NextWeekday),
+            "nearest-weekday" => Some(Substitute::NearestWeekday),
+            _ => None
+        }
+    }
+
+    /// The day off instead of `date`, None if it isnt on a weekend.
+    /// `taken` tells if a day is a holiday already, the next weekday skips those
+    pub fn date_for(&self, date: NaiveDate, taken: impl Fn(NaiveDate) -> bool) -> Option<NaiveDate> {
+        match (self, date.weekday()) {
+            (_, Weekday::Mon | Weekday::Tue | Weekday::Wed | Weekday::Thu | Weekday::Fri) => None,
+            (Substitute::NearestWeekday, Weekday::Sat) => date.pred_opt(),
+            (Substitute::NearestWeekday, _) => date.succ_opt(),
+            (Substitute::NextWeekday, _) => {
+                let mut day = date.succ_opt()?;
+                while matches!(day.weekday(), Weekday::Sat | Weekday::Sun) || taken(day) {
+                    day = day.succ_opt()?;
+                }
+                Some(day)
+            }
+        }
+    }
+
+    /// Put after the holiday's name on the substitute day
+    pub fn label(&self) -> &'static str {
+        match self {
+            Substitute::NextWeekday => "substitute day",
+            Substitute::NearestWeekday => "observed"
+        }
+    }
+}
+
+/// Western Easter Sunday, with the anonymous Gregorian algorithm
+pub fn easter(year: i32) -> Option<NaiveDate> {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32)
+}
+
+/// Orthodox Easter Sunday. It's calculated on the Julian calendar, then moved to the Gregorian one
+pub fn orthodox_easter(year: i32) -> Option<NaiveDate> {
+    let a = year % 4;
+    let b = year % 7;
+    let c = year % 19;
+    let d = (19 * c + 15) % 30;
+    let e = (2 * a + 4 * b - d + 34) % 7;
+    let month = (d + e + 114) / 31;
+    let day = (d + e + 114) % 31 + 1;
+
+    // The difference between the calendars, 13 days between 1900 and 2099
+    let difference = year / 100 - year / 400 - 2;
+
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32)?
+        .checked_add_days(Days::new(difference as u64))
+}
+
+fn offset(date: NaiveDate, days: i64) -> Option<NaiveDate> {
+    match days >= 0 {
+        true => date.checked_add_days(Days::new(days as u64)),
+        false => date.checked_sub_days(Days::new(days.unsigned_abs()))
+    }
+}
+
+/// Parses a month's name (january, jan) or number (1-12)
+fn parse_month(text: &str) -> Option<u32> {
+    match text.parse::<u32>() {
+        Ok(n) if (1..=12).contains(&n) => Some(n),
+        Ok(_) => None,
+        Err(_) => text.parse::<Month>().ok().map(|m| m.number_from_month())
+    }
+}
+
+/// Parses a month and a day like 12-25
+fn parse_month_day(text: &str) -> Option<(u32, u32)> {
+    let (month, day) = text.split_once('-')?;
+    let (month, day) = (month.trim().parse::<u32>().ok()?, day.trim().parse::<u32>().ok()?);
+
+    // 2000 is a leap year, so 02-29 is valid
+    NaiveDate::from_ymd_opt(2000, month, day).map(|_| (month, day))
+}
+
+impl Rule {
+    /// Parses a key and its value from a dataset file, None if the key isnt a rule or the value is invalid
+    pub fn parse(key: &str, value: &str) -> Option<Self> {
+        let value = value.trim().to_lowercase();
+
+        match key {
+            "date" => parse_month_day(&value).map(|(month, day)| Rule::Fixed { month, day }),
+            "easter" => value.parse().ok().map(Rule::Easter),
+            "orthodox-easter" => value.parse().ok().map(Rule::OrthodoxEaster),
+            "weekday" => {
+                let parts: Vec<&str> = value.split_whitespace().collect();
+                let [n, weekday, month] = parts[..] else {
+                    return None;
+                };
+
+                let n = match n {
+                    "last" => -1,
+                    n => n.parse::<i8>().ok().filter(|n| (1..=5).contains(n) || *n == -1)?
+                };
+
+                Some(Rule::NthWeekday { month: parse_month(month)?, weekday: weekday.parse().ok()?, n })
+            },
+            "on-or-after" => {
+                let (date, weekday) = value.split_once(' ')?;
+                let (month, day) = parse_month_day(date)?;
+                Some(Rule::OnOrAfter { month, day, weekday: weekday.trim().parse().ok()? })
+            },
+            _ => None
+        }
+    }
+
+    /// The date in a year, None if there is none (like the 5th monday of a month, or 02-29 in a common year)
+    pub fn date_in(&self, year: i32) -> Option<NaiveDate> {
+        match *self {
+            Rule::Fixed { month, day } => NaiveDate::from_ymd_opt(year, month, day),
+            Rule::Easter(days) => offset(easter(year)?, days),
+            Rule::OrthodoxEaster(days) => offset(orthodox_easter(year)?, days),
+            Rule::NthWeekday { month, weekday, n: -1 } => {
+                let next_month = match month {
+                    12 => NaiveDate::from_ymd_opt(year + 1, 1, 1)?,
+                    m => NaiveDate::from_ymd_opt(year, m + 1, 1)?
+                };
+                let last = next_month.pred_opt()?;
+                let back = (7 + last.weekday().num_days_from_monday() - weekday.num_days_from_monday()) % 7;
+
+                last.checked_sub_days(Days::new(back as u64))
+            },
+            Rule::NthWeekday { month, weekday, n } => {
+                NaiveDate::from_weekday_of_month_opt(year, month, weekday, n as u8)
+            },
+            Rule::OnOrAfter { month, day, weekday } => {
+                let start = NaiveDate::from_ymd_opt(year, month, day)?;
+                let ahead = (7 + weekday.num_days_from_monday() - start.weekday().num_days_from_monday()) % 7;
+
+                start.checked_add_days(Days::new(ahead as u64))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn easter_in_known_years() {
+        assert_eq!(easter(2000), Some(date(2000, 4, 23)));
+        assert_eq!(easter(2011), Some(date(2011, 4, 24)));
+        assert_eq!(easter(2019), Some(date(2019, 4, 21)));
+        assert_eq!(easter(2024), Some(date(2024, 3, 31)));
+        assert_eq!(easter(2025), Some(date(2025, 4, 20)));
+        assert_eq!(easter(2026), Some(date(2026, 4, 5)));
+        // The earliest and the latest it can be
+        assert_eq!(easter(1818), Some(date(1818, 3, 22)));
+        assert_eq!(easter(2038), Some(date(2038, 4, 25)));
+    }
+
+    #[test]
+    fn orthodox_easter_in_known_years() {
+        assert_eq!(orthodox_easter(2010), Some(date(2010, 4, 4)));
+        assert_eq!(orthodox_easter(2021), Some(date(2021, 5, 2)));
+        assert_eq!(orthodox_easter(2023), Some(date(2023, 4, 16)));
+        assert_eq!(orthodox_easter(2024), Some(date(2024, 5, 5)));
+        assert_eq!(orthodox_easter(2025), Some(date(2025, 4, 20)));
+        assert_eq!(orthodox_easter(2026), Some(date(2026, 4, 12)));
+    }
+
+    #[test]
+    fn last_and_fifth_weekday() {
+        let rule = |value: &str| Rule::parse("weekday", value).unwrap();
+
+        assert_eq!(rule("last monday may").date_in(2026), Some(date(2026, 5, 25)));
+        // The last day of the month is the weekday itself
+        assert_eq!(rule("last friday december").date_in(2027), Some(date(2027, 12, 31)));
+        assert_eq!(rule("-1 sunday february").date_in(2026), Some(date(2026, 2, 22)));
+        assert_eq!(rule("5 thursday january").date_in(2026), Some(date(2026, 1, 29)));
+        assert_eq!(rule("5 monday february").date_in(2026), None);
+        assert_eq!(Rule::parse("weekday", "6 monday may"), None);
+        assert_eq!(Rule::parse("weekday", "0 monday may"), None);
+    }
+
+    #[test]
+    fn substitute_days() {
+        let none_taken = |_| false;
+
+        // 2027-12-25 is a saturday, 2027-12-26 a sunday
+        assert_eq!(Substitute::NextWeekday.date_for(date(2027, 12, 25), none_taken), Some(date(2027, 12, 27)));
+        assert_eq!(Substitute::NextWeekday.date_for(date(2027, 12, 26), |d| d == date(2027, 12, 27)), Some(date(2027, 12, 28)));
+        assert_eq!(Substitute::NextWeekday.date_for(date(2026, 12, 25), none_taken), None);
+
+        // 2026-07-04 is a saturday, 2027-07-04 a sunday
+        assert_eq!(Substitute::NearestWeekday.date_for(date(2026, 7, 4), none_taken), Some(date(2026, 7, 3)));
+        assert_eq!(Substitute::NearestWeekday.date_for(date(2027, 7, 4), none_taken), Some(date(2027, 7, 5)));
+        assert_eq!(Substitute::NearestWeekday.date_for(date(2028, 7, 4), none_taken), None);
+    }
+}
@@ -1,14 +1,14 @@
-use gtk::{ Application, Box as Gbox, CssProvider, gdk::Display, glib, prelude::* };
+use gtk::{ Application, Box as Gbox, glib, prelude::* };
 use gtk_ls::{self, LayerShell, Layer};
 use gtk::gdk;
 
 
 mod weather;
+mod astronomy;
 mod units;
 mod upower;
-use upower::UPower;
 
-use crate::{ui_elements::build_current_weather, units::Units};
+use crate::{ui_elements::{build_battery, build_current_weather, build_sun_arc, set_weather_css_classes}, units::Units};
 use units::{Speed, Precipitation, Temperature};
 
 
@@ -16,29 +16,103 @@ use units::{Speed, Precipitation, Temperature};
 use async_channel;
 use std::sync::OnceLock;
 
-use chrono;
-use std::time::Duration;
 
 mod ui_elements;
-use ui_elements::{build_clock};
+use ui_elements::{build_clock, build_timers, build_world_clocks};
 
 // Custom GObjects
 mod global_state;
 use global_state::GlobalState;
 
 mod clock;
+mod system_clock;
+mod digital_clock;
+mod analog_clock;
+mod month_view;
+
+mod config;
+use config::Config;
+mod style;
+mod theme;
 
 mod weather_state;
 
+mod calendar;
+mod holidays;
+use holidays::Holidays;
+mod timers;
+mod pomodoro;
+mod pomodoro_ring;
+mod battery_graph;
+mod battery_row;
+use pomodoro_ring::build_pomodoro;
+mod notification;
+mod agenda_state;
+
 
 const APP_ID: &str = "dinfo.oil653";
 static DATE_FORMAT: OnceLock<String> = OnceLock::new();
 static UNITS: OnceLock<Units> = OnceLock::new();
+static CONFIG: OnceLock<Config> = OnceLock::new();
+static HOLIDAYS: OnceLock<Holidays> = OnceLock::new();
 
 fn build_ui(app: &Application) {
     let state = GlobalState::new();
     let clock_state = state.clock().clone().expect("Clock state returned None. (This shouldnt happen)");
     let current_weather_state = state.weather().clone().expect("Clock state returned None. (This shouldnt happen)");
+    let timers = state.timers().expect("Timers state returned None. (This shouldnt happen)");
+    let pomodoro = state.pomodoro().expect("Pomodoro state returned None. (This shouldnt happen)");
+    let upower = state.upower().expect("UPower state returned None. (This shouldnt happen)");
+    upower::low_battery::watch(&upower);
+
+    // The timers and the pomodoro are checked on the clock's ticks, which come every second while one of them runs
+    let update_fast_ticks = glib::clone!(
+        #[weak]
+        clock_state,
+        #[weak]
+        timers,
+        #[weak]
+        pomodoro,
+        move || clock_state.set_fast_ticks(timers.active() || pomodoro.running())
+    );
+    update_fast_ticks();
+    timers.connect_active_notify(glib::clone!(
+        #[strong]
+        update_fast_ticks,
+        move |_| update_fast_ticks()
+    ));
+    pomodoro.connect_running_notify(move |_| update_fast_ticks());
+
+    clock_state.connect_tick(glib::clone!(
+        #[weak]
+        timers,
+        #[weak]
+        pomodoro,
+        move |_| {
+            let now = chrono::Local::now();
+            timers.tick(now);
+            pomodoro.tick(now);
+        }
+    ));
+    timers::add_actions(app, &timers);
+    pomodoro::add_actions(app, &pomodoro);
+
+    // The agenda is only shown if there are calendars to read
+    let calendars = CONFIG.get().unwrap().calendar.agenda_paths();
+    let agenda_state = match calendars.is_empty() {
+        true => None,
+        false => state.agenda()
+    };
+    if let Some(agenda_state) = &agenda_state {
+        // The cache directories need to exist to be watched
+        for caldav in &CONFIG.get().unwrap().calendar.caldav {
+            if let Err(e) = std::fs::create_dir_all(calendar::caldav::cache_dir(&caldav.name)) {
+                eprintln!("Failed to create the cache of calendar {}: {e}", caldav.name);
+            }
+        }
+        calendar::caldav::start(CONFIG.get().unwrap().calendar.caldav.clone());
+        calendar::load_and_watch(agenda_state, calendars);
+    }
 
     // Parse current weather data
     let (current_snd, current_rcv) = async_channel::bounded(1);
@@ -57,7 +131,7 @@ fn build_ui(app: &Application) {
     ));
 
     let monitors = gdk::Display::default().expect("Failed to get all monitors").monitors();
-    for monitor in monitors.iter().flatten() {
+    for monitor in monitors.iter::<gdk::Monitor>().flatten() {
         // Channel needed to send to weather data
         let current_snd = current_snd.clone();
 
@@ -65,17 +139,38 @@ fn build_ui(app: &Application) {
             .orientation(gtk::Orientation::Vertical)
             .build();
 
-        let clock = build_clock(clock_state.clone());
+        let face = CONFIG.get().unwrap().clock_face_for(monitor.connector().as_deref());
+        let clock = build_clock(clock_state.clone(), face, agenda_state.as_ref());
 
 
         let ( current_weather, update_internal) = build_current_weather(&current_weather_state, current_snd);
+        let ( sun_arc, update_sun_arc ) = build_sun_arc(&current_weather_state);
 
         current_weather_state.connect_is_parsing_notify(move |_| {
             update_internal();
+            update_sun_arc();
         });
 
         main_box.append(&clock);
-        main_box.append(&current_weather);
+        main_box.append(&build_timers(&timers, &clock_state));
+        if CONFIG.get().unwrap().pomodoro.enabled {
+            main_box.append(&build_pomodoro(&pomodoro, &clock_state));
+        }
+        if let Some(world_clocks) = build_world_clocks(&clock_state) {
+            main_box.append(&world_clocks);
+        }
+        let battery = build_battery(&upower);
+
+        // The weather and the battery island side by side
+        let islands = Gbox::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .halign(gtk::Align::Center)
+            .build();
+        islands.append(&current_weather);
+        islands.append(&battery);
+
+        main_box.append(&islands);
+        main_box.append(&sun_arc);
 
         //  =========> WINDOW <=========
         let window = gtk::ApplicationWindow::builder()
@@ -94,44 +189,22 @@ fn build_ui(app: &Application) {
         window.set_exclusive_zone(0);
         window.set_monitor(Some(&monitor));
 
+        // Retint the whole window with the weather
+        current_weather_state.connect_is_parsing_notify(glib::clone!(
+            #[weak]
+            window,
+            move |state| {
+                set_weather_css_classes(&window, state.get_current().as_ref());
+            }
+        ));
+
         window.present();
     }
 
     // Animate, and drive the clock state changes
-    glib::spawn_future_local(async move {
-        let state = clock_state;
-        // println!("{:?}", state);
-        loop {
-            let now = chrono::Local::now();
-            state.set_clock2_label(now.format("%H:%M:%S").to_string());
-            state.clock2_should_fade(false);
-            glib::timeout_future(Duration::from_millis(50)).await;
-            state.clock1_should_fade(true);
-
-            glib::timeout_future(Duration::from_millis(950)).await;
-
-            let now = chrono::Local::now();
-            state.set_clock1_label(now.format("%H:%M:%S").to_string());
-            state.clock1_should_fade(false);
-            glib::timeout_future(Duration::from_millis(50)).await;
-            state.clock2_should_fade(true);
-
-            glib::timeout_future(Duration::from_millis(950)).await;
-        }
-    });
-}
-
-fn load_css() {
-    // Load the CSS file and add it to the provider
-    let provider = CssProvider::new();
-    provider.load_from_string(include_str!("style.css"));
-    
-    // Add the provider to the default screen
-    gtk::style_context_add_provider_for_display(
-        &Display::default().expect("Could not connect to a display."),
-        &provider,
-        gtk::STYLE_PROVIDER_PRIORITY_USER,
-    );
+    let format = CONFIG.get().unwrap().clock.format.clone();
+    system_clock::watch(&clock_state);
+    glib::spawn_future_local(clock::drive_clock(clock_state, format));
 }
 
 fn main() -> glib::ExitCode {
@@ -140,33 +213,16 @@ fn main() -> glib::ExitCode {
         std::env::set_var("GSK_RENDERER", "cairo");
     }
 
+    CONFIG.set(Config::load()).expect("Failed to set CONFIG static");
     UNITS.set(Units::new(Speed::Kmh, Temperature::Celsius, Precipitation::Mm)).expect("Failed to set UNITS static");
-    DATE_FORMAT.set("%d/%m/%Y".to_string()).expect("Failed to set DATE_FORMAT static");
+    DATE_FORMAT.set(CONFIG.get().unwrap().clock.date_format.clone()).expect("Failed to set DATE_FORMAT static");
+    HOLIDAYS.set(Holidays::load(&CONFIG.get().unwrap().holidays)).expect("Failed to set HOLIDAYS static");
 
     let app = Application::builder()
         .application_id(APP_ID)
         .build();
 
-    glib::spawn_future_local(async move {
-        let meow = UPower::new().await;
-
-        meow.connect_closure("devices-changed", false, glib::closure_local!(   
-            move |obj: &UPower| {
-                println!("Got signal thingy");
-
-                glib::spawn_future_local(glib::clone!(
-                    #[strong]
-                    obj,
-                    async move {
-                        let devices = obj.get_devices().await;
-                        println!("{:#?}", devices);
-                    }
-                ));
-            }
-        ));
-    });
-
-    app.connect_startup(|_| load_css());
+    app.connect_startup(|_| style::load_css());
     app.connect_activate(build_ui);
 
     app.run()
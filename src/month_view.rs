@@ -0,0 +1,215 @@
+use gtk::{ Align, Box as Gbox, Button, Grid, Label, glib, prelude::* };
+use chrono::{ Datelike, Duration, Local, Months, NaiveDate, Weekday };
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::config::CalendarConfig;
+
+// A month grid like gtk::Calendar, but with a configurable first day of the week.
+// It always shows 6 weeks, so the popover doesnt change size while navigating.
+
+const WEEKS_SHOWN: u64 = 6;
+
+/// The first day of the month `date` is in
+fn first_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).expect("Every month has a first day")
+}
+
+/// The ISO week number of a row. Every 7 days contain exactly one thursday,
+/// and the ISO week is the one its thursday is in, no matter which day the row starts on
+fn row_week_number(row_start: NaiveDate) -> u32 {
+    row_start
+        .iter_days()
+        .take(7)
+        .find(|d| d.weekday() == Weekday::Thu)
+        .expect("7 days always contain a thursday")
+        .iso_week()
+        .week()
+}
+
+/// Removes every child of the grid, and fills it with the days of `month`
+fn fill_grid(grid: &Grid, month: NaiveDate, config: &CalendarConfig) {
+    while let Some(child) = grid.first_child() {
+        grid.remove(&child);
+    }
+
+    let today = Local::now().date_naive();
+    let column_offset = if config.week_numbers { 1 } else { 0 };
+
+    // Day names
+    let mut weekday = config.week_start;
+    for column in 0..7 {
+        let name = Label::builder()
+            .label(&weekday.to_string()[..2])
+            .css_classes(["text", "month_view_weekday"])
+            .build();
+        grid.attach(&name, column + column_offset, 0, 1, 1);
+        weekday = weekday.succ();
+    }
+
+    // Go back to the start of the week the 1st is in
+    let first = first_of_month(month);
+    let days_before = first.weekday().days_since(config.week_start);
+    let grid_start = first - Duration::days(days_before as i64);
+
+    for row in 0..WEEKS_SHOWN {
+        let row_start = grid_start + Duration::weeks(row as i64);
+
+        if config.week_numbers {
+            let week = Label::builder()
+                .label(row_week_number(row_start).to_string())
+                .css_classes(["text", "month_view_week"])
+                .build();
+            grid.attach(&week, 0, row as i32 + 1, 1, 1);
+        }
+
+        for (column, day) in row_start.iter_days().take(7).enumerate() {
+            let label = Label::builder()
+                .label(day.day().to_string())
+                .css_classes(["text", "month_view_day"])
+                .build();
+
+            if day.month() != month.month() {
+                label.add_css_class("other-month");
+            }
+            if matches!(day.weekday(), Weekday::Sat | Weekday::Sun) {
+                label.add_css_class("weekend");
+            }
+            if day == today {
+                label.add_css_class("today");
+            }
+
+            grid.attach(&label, column as i32 + column_offset, row as i32 + 1, 1, 1);
+        }
+    }
+}
+
+/// Builds the month grid with buttons to move between the months.
+/// The returned closure jumps back to the current month
+pub fn build_month_view(config: &CalendarConfig) -> (Gbox, Box<dyn Fn()>) {
+    let month = Rc::new(Cell::new(first_of_month(Local::now().date_naive())));
+
+    let container = Gbox::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .name("month_view")
+        .css_classes(["month_view"])
+        .spacing(6)
+        .build();
+
+    let previous = Button::builder()
+        .label("‹")
+        .css_classes(["text", "month_view_nav"])
+        .tooltip_text("Previous month")
+        .build();
+
+    // Clicking the title goes back to the current month
+    let title = Button::builder()
+        .css_classes(["text", "month_view_title"])
+        .tooltip_text("Go to today")
+        .hexpand(true)
+        .build();
+
+    let next = Button::builder()
+        .label("›")
+        .css_classes(["text", "month_view_nav"])
+        .tooltip_text("Next month")
+        .build();
+
+    let header = Gbox::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .build();
+    header.append(&previous);
+    header.append(&title);
+    header.append(&next);
+
+    let grid = Grid::builder()
+        .column_homogeneous(true)
+        .row_homogeneous(true)
+        .column_spacing(4)
+        .row_spacing(2)
+        .halign(Align::Center)
+        .build();
+
+    container.append(&header);
+    container.append(&grid);
+
+    let config = config.clone();
+    let show: Rc<dyn Fn(NaiveDate)> = Rc::new(glib::clone!(
+        #[strong]
+        month,
+        #[weak]
+        title,
+        #[weak]
+        grid,
+        move |new_month: NaiveDate| {
+            month.set(new_month);
+            title.set_label(&new_month.format("%B %Y").to_string());
+            fill_grid(&grid, new_month, &config);
+        }
+    ));
+
+    show(month.get());
+
+    previous.connect_clicked(glib::clone!(
+        #[strong]
+        show,
+        #[strong]
+        month,
+        move |_| show(month.get() - Months::new(1))
+    ));
+
+    next.connect_clicked(glib::clone!(
+        #[strong]
+        show,
+        #[strong]
+        month,
+        move |_| show(month.get() + Months::new(1))
+    ));
+
+    title.connect_clicked(glib::clone!(
+        #[strong]
+        show,
+        move |_| show(first_of_month(Local::now().date_naive()))
+    ));
+
+    let go_to_today: Box<dyn Fn()> = Box::new(move || show(first_of_month(Local::now().date_naive())));
+
+    (container, go_to_today)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn week_of_row(y: i32, m: u32, d: u32) -> u32 {
+        row_week_number(NaiveDate::from_ymd_opt(y, m, d).unwrap())
+    }
+
+    #[test]
+    fn week_numbers_of_monday_rows() {
+        assert_eq!(week_of_row(2026, 10, 19), 43);
+        // 2024-12-30 is in the first week of 2025
+        assert_eq!(week_of_row(2024, 12, 30), 1);
+        // 2026 starts on a thursday, so it has 53 weeks
+        assert_eq!(week_of_row(2026, 12, 28), 53);
+        assert_eq!(week_of_row(2027, 1, 4), 1);
+    }
+
+    #[test]
+    fn week_numbers_of_sunday_rows() {
+        assert_eq!(week_of_row(2026, 10, 18), 43);
+        // The sunday is still in the last week of 2024, but the row is mostly the first week of 2025
+        assert_eq!(week_of_row(2024, 12, 29), 1);
+        assert_eq!(week_of_row(2026, 12, 27), 53);
+        assert_eq!(week_of_row(2027, 1, 3), 1);
+    }
+
+    #[test]
+    fn week_numbers_of_saturday_rows() {
+        // 2020-12-26 to 2021-01-01, the thursday is 2020-12-31
+        assert_eq!(week_of_row(2020, 12, 26), 53);
+        assert_eq!(week_of_row(2021, 1, 2), 1);
+        assert_eq!(week_of_row(2025, 12, 27), 1);
+    }
+}
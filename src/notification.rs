@@ -0,0 +1,57 @@
+use gtk::{ MediaFile, gio, prelude::* };
+
+use std::cell::RefCell;
+use std::path::Path;
+
+// Desktop notifications are sent through the GApplication, so they show up with dinfo's name and icon.
+
+thread_local! {
+    // The sounds being played, they stop if they are dropped
+    static PLAYING: RefCell<Vec<MediaFile>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Sends a desktop notification. A new one with the same id replaces the old one
+pub fn send(id: &str, title: &str, body: &str, priority: gio::NotificationPriority) {
+    let Some(app) = gio::Application::default() else {
+        eprintln!("Can't send notification '{title}', there is no application");
+        return;
+    };
+
+    let notification = gio::Notification::new(title);
+    notification.set_body(Some(body));
+    notification.set_priority(priority);
+
+    app.send_notification(Some(id), &notification);
+}
+
+/// Takes back a sent notification, if it's still shown
+pub fn withdraw(id: &str) {
+    if let Some(app) = gio::Application::default() {
+        app.withdraw_notification(id);
+    }
+}
+
+/// Plays a sound file once
+pub fn play_sound(path: &Path) {
+    if !path.exists() {
+        eprintln!("Can't play sound {}, the file doesnt exist", path.display());
+        return;
+    }
+
+    let media = MediaFile::for_filename(path);
+
+    media.connect_ended_notify(|media| {
+        if media.is_ended() {
+            PLAYING.with(|p| p.borrow_mut().retain(|m| m != media));
+        }
+    });
+    media.connect_error_notify(|media| {
+        if let Some(e) = media.error() {
+            eprintln!("Failed to play sound: {e}");
+            PLAYING.with(|p| p.borrow_mut().retain(|m| m != media));
+        }
+    });
+
+    media.play();
+    PLAYING.with(|p| p.borrow_mut().push(media));
+}
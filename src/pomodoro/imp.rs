@@ -0,0 +1,53 @@
+use std::cell::{ Cell, RefCell };
+
+use std::sync::OnceLock;
+use glib::subclass::Signal;
+
+use glib::Properties;
+use gtk::glib;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+
+use chrono::NaiveDate;
+
+use super::Session;
+use super::stats::DayStats;
+
+#[derive(Default, Properties)]
+#[properties[wrapper_type = super::Pomodoro]]
+pub struct Pomodoro {
+    /// True while a phase counts down (not idle or paused), the clock ticks every second then
+    #[property(get, set)]
+    running: Cell<bool>,
+    /// Finished work sessions since the last long break
+    #[property(get, set)]
+    cycle: Cell<u32>,
+    pub session: RefCell<Session>,
+    /// The statistics of a day, so they arent read from the file on every tick
+    pub today: RefCell<Option<(NaiveDate, DayStats)>>
+}
+
+impl Pomodoro {
+    pub fn changed(&self) {
+        let obj = self.obj();
+        obj.set_running(self.session.borrow().is_running());
+        obj.emit_by_name::<()>("changed", &[]);
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for Pomodoro {
+    const NAME: &'static str = "Pomodoro";
+    type Type = super::Pomodoro;
+}
+
+#[glib::derived_properties]
+impl ObjectImpl for Pomodoro {
+    fn signals() -> &'static [Signal] {
+        static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+        SIGNALS.get_or_init(|| {
+            // The phase changed, or it was started, paused or reset
+            vec![Signal::builder("changed").build()]
+        })
+    }
+}
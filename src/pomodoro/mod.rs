@@ -0,0 +1,333 @@
+use glib::Object;
+use gtk::{ Application, gio, prelude::*, glib::{ self, subclass::types::ObjectSubclassIsExt } };
+use chrono::{ DateTime, Local };
+
+use std::fmt;
+use std::time::Duration;
+
+mod imp;
+
+pub mod stats;
+use stats::DayStats;
+
+use crate::config::PomodoroConfig;
+use crate::notification;
+
+// Work and break phases following each other, with a long break after every few work sessions.
+// Like the timers it's checked on the clock's ticks, and controlled by clicking or with the actions:
+// gapplication action dinfo.oil653 pomodoro-toggle
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    /// Not started yet, or reset
+    Idle,
+    Work,
+    ShortBreak,
+    LongBreak
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Idle => "Pomodoro",
+            Self::Work => "Focus",
+            Self::ShortBreak => "Short break",
+            Self::LongBreak => "Long break",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Phase {
+    /// The css class of the widget in this phase
+    pub fn css_class(&self) -> &'static str {
+        match self {
+            Self::Idle => "pomodoro-idle",
+            Self::Work => "pomodoro-work",
+            Self::ShortBreak => "pomodoro-short-break",
+            Self::LongBreak => "pomodoro-long-break",
+        }
+    }
+
+    /// The phase after this one. `cycle` is the finished work sessions since the last long break,
+    /// after `cycles` of them it's a long break
+    pub fn next(&self, cycle: u32, cycles: u32) -> Phase {
+        match self {
+            Self::Work if cycle >= cycles => Self::LongBreak,
+            Self::Work => Self::ShortBreak,
+            _ => Self::Work
+        }
+    }
+
+    pub const ALL_CSS_CLASSES: [&str; 4] = ["pomodoro-idle", "pomodoro-work", "pomodoro-short-break", "pomodoro-long-break"];
+}
+
+/// Where the current phase is at
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub phase: Phase,
+    pub length: Duration,
+    /// When the phase ends, None while it's paused (or idle)
+    end: Option<DateTime<Local>>,
+    /// Time left when it was paused
+    paused_remaining: Duration
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session { phase: Phase::Idle, length: Duration::ZERO, end: None, paused_remaining: Duration::ZERO }
+    }
+}
+
+impl Session {
+    fn start(phase: Phase, length: Duration, now: DateTime<Local>) -> Self {
+        Session {
+            phase,
+            length,
+            end: chrono::Duration::from_std(length).ok().map(|l| now + l),
+            paused_remaining: length
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.end.is_some()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.phase != Phase::Idle && self.end.is_none()
+    }
+
+    pub fn remaining(&self, now: DateTime<Local>) -> Duration {
+        match self.end {
+            Some(end) => (end - now).to_std().unwrap_or_default(),
+            None => self.paused_remaining
+        }
+    }
+
+    /// How much of the phase is done, 0-1
+    pub fn progress(&self, now: DateTime<Local>) -> f64 {
+        if self.length.is_zero() {
+            return 0.0;
+        }
+        1.0 - self.remaining(now).as_secs_f64() / self.length.as_secs_f64()
+    }
+}
+
+glib::wrapper!{
+    pub struct Pomodoro(ObjectSubclass<imp::Pomodoro>);
+}
+
+impl Pomodoro {
+    pub fn new() -> Self {
+        Object::builder()
+            .property("running", false)
+            .property("cycle", 0u32)
+            .build()
+    }
+
+    fn config() -> &'static PomodoroConfig {
+        &crate::CONFIG.get().unwrap().pomodoro
+    }
+
+    pub fn session(&self) -> Session {
+        self.imp().session.borrow().clone()
+    }
+
+    /// The statistics of today, only read from the file again when the date changes
+    pub fn today(&self) -> DayStats {
+        let date = Local::now().date_naive();
+        let cached = self.imp().today.borrow().filter(|(cached_date, _)| *cached_date == date);
+        if let Some((_, today)) = cached {
+            return today;
+        }
+
+        let today = stats::day(date);
+        self.imp().today.replace(Some((date, today)));
+        today
+    }
+
+    pub fn connect_changed<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure("changed", false, glib::closure_local!(move |pomodoro: &Self| f(pomodoro)))
+    }
+
+    fn start_phase(&self, phase: Phase, now: DateTime<Local>) {
+        let config = Self::config();
+        let length = match phase {
+            Phase::Idle => Duration::ZERO,
+            Phase::Work => config.work,
+            Phase::ShortBreak => config.short_break,
+            Phase::LongBreak => config.long_break
+        };
+
+        self.imp().session.replace(match phase {
+            Phase::Idle => Session::default(),
+            _ => Session::start(phase, length, now)
+        });
+    }
+
+    /// Starts a work session if it's idle, otherwise pauses or continues the current phase
+    pub fn toggle(&self) {
+        let now = Local::now();
+        let session = self.session();
+
+        match (session.phase, session.end) {
+            (Phase::Idle, _) => self.start_phase(Phase::Work, now),
+            (_, Some(_)) => {
+                let mut session = self.imp().session.borrow_mut();
+                session.paused_remaining = session.remaining(now);
+                session.end = None;
+            },
+            (_, None) => {
+                let mut session = self.imp().session.borrow_mut();
+                session.end = chrono::Duration::from_std(session.paused_remaining).ok().map(|r| now + r);
+            }
+        }
+
+        self.imp().changed();
+    }
+
+    /// Ends the current phase without counting it, and starts the next one
+    pub fn skip(&self) {
+        let phase = self.session().phase;
+        if phase == Phase::Idle {
+            return;
+        }
+
+        if phase == Phase::LongBreak {
+            self.set_cycle(0);
+        }
+        self.start_phase(self.next_phase(phase), Local::now());
+        self.imp().changed();
+    }
+
+    /// Stops, and starts the cycles over
+    pub fn reset(&self) {
+        self.start_phase(Phase::Idle, Local::now());
+        self.set_cycle(0);
+        self.imp().changed();
+    }
+
+    /// The phase after `phase`, a long break after every few work sessions
+    fn next_phase(&self, phase: Phase) -> Phase {
+        phase.next(self.cycle(), Self::config().cycles)
+    }
+
+    /// Moves on to the next phase if the current one is over
+    pub fn tick(&self, now: DateTime<Local>) {
+        let session = self.session();
+        if !session.is_running() || !session.remaining(now).is_zero() {
+            return;
+        }
+
+        if session.phase == Phase::Work {
+            self.set_cycle(self.cycle() + 1);
+
+            match stats::add_session(now.date_naive(), session.length) {
+                Ok(today) => {
+                    println!("Finished a pomodoro, {} today", today.sessions);
+                    self.imp().today.replace(Some((now.date_naive(), today)));
+                },
+                Err(e) => {
+                    eprintln!("Failed to save pomodoro statistics: {e}");
+                    self.imp().today.replace(None);
+                }
+            }
+        }
+
+        let next = self.next_phase(session.phase);
+        if session.phase == Phase::LongBreak {
+            self.set_cycle(0);
+        }
+        self.start_phase(next, now);
+
+        let (title, body) = match next {
+            Phase::Work => ("Back to work", format!("Focus for {} minutes", Self::config().work.as_secs() / 60)),
+            Phase::ShortBreak => ("Take a short break", format!("{} minutes, {} of {} sessions done", Self::config().short_break.as_secs() / 60, self.cycle(), Self::config().cycles)),
+            Phase::LongBreak => ("Take a long break", format!("{} minutes, all {} sessions are done", Self::config().long_break.as_secs() / 60, Self::config().cycles)),
+            Phase::Idle => ("Pomodoro stopped", String::new())
+        };
+        notification::send("pomodoro", title, &body, gio::NotificationPriority::High);
+
+        self.imp().changed();
+    }
+}
+
+/// Adds the actions that control the pomodoro to the application: pomodoro-toggle, pomodoro-skip, pomodoro-reset
+pub fn add_actions(app: &Application, pomodoro: &Pomodoro) {
+    let action = |name: &str, f: fn(&Pomodoro)| {
+        gio::ActionEntry::builder(name)
+            .activate(glib::clone!(
+                #[weak]
+                pomodoro,
+                move |_: &Application, _, _| f(&pomodoro)
+            ))
+            .build()
+    };
+
+    app.add_action_entries([
+        action("pomodoro-toggle", Pomodoro::toggle),
+        action("pomodoro-skip", Pomodoro::skip),
+        action("pomodoro-reset", Pomodoro::reset),
+    ]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    fn at(h: u32, m: u32, s: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 10, 19, h, m, s).unwrap()
+    }
+
+    #[test]
+    fn running_session() {
+        let session = Session::start(Phase::Work, 25 * MINUTE, at(10, 0, 0));
+        assert!(session.is_running());
+        assert!(!session.is_paused());
+
+        assert_eq!(session.remaining(at(10, 0, 0)), 25 * MINUTE);
+        assert_eq!(session.remaining(at(10, 10, 30)), 14 * MINUTE + Duration::from_secs(30));
+        assert_eq!(session.progress(at(10, 0, 0)), 0.0);
+        assert!((session.progress(at(10, 5, 0)) - 0.2).abs() < 1e-9);
+        // Over, and past the end
+        assert_eq!(session.remaining(at(10, 25, 0)), Duration::ZERO);
+        assert_eq!(session.remaining(at(11, 0, 0)), Duration::ZERO);
+        assert_eq!(session.progress(at(11, 0, 0)), 1.0);
+    }
+
+    #[test]
+    fn paused_session() {
+        let session = Session { phase: Phase::ShortBreak, length: 5 * MINUTE, end: None, paused_remaining: 2 * MINUTE };
+        assert!(!session.is_running());
+        assert!(session.is_paused());
+
+        // Time doesnt pass while it's paused
+        assert_eq!(session.remaining(at(10, 0, 0)), 2 * MINUTE);
+        assert_eq!(session.remaining(at(12, 0, 0)), 2 * MINUTE);
+        assert!((session.progress(at(12, 0, 0)) - 0.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn idle_session() {
+        let session = Session::default();
+        assert!(!session.is_running());
+        assert!(!session.is_paused());
+        assert_eq!(session.progress(at(10, 0, 0)), 0.0);
+    }
+
+    #[test]
+    fn long_break_after_cycles() {
+        // The cycle counts the work session that just ended
+        assert_eq!(Phase::Work.next(1, 4), Phase::ShortBreak);
+        assert_eq!(Phase::Work.next(3, 4), Phase::ShortBreak);
+        assert_eq!(Phase::Work.next(4, 4), Phase::LongBreak);
+        assert_eq!(Phase::Work.next(1, 1), Phase::LongBreak);
+
+        assert_eq!(Phase::ShortBreak.next(2, 4), Phase::Work);
+        assert_eq!(Phase::LongBreak.next(4, 4), Phase::Work);
+        assert_eq!(Phase::Idle.next(0, 4), Phase::Work);
+    }
+}
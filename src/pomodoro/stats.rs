@@ -0,0 +1,139 @@
+use gtk::glib::{ self, KeyFile, KeyFileFlags };
+use chrono::NaiveDate;
+
+use std::error::Error;
+use std::path::{ Path, PathBuf };
+use std::time::Duration;
+
+// Finished work sessions are counted per day in $XDG_DATA_HOME/dinfo/pomodoro.ini:
+//
+// [2025-01-01]
+// sessions=6
+// focus-minutes=150
+
+/// What was done on a day
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DayStats {
+    /// Finished work sessions (skipped ones dont count)
+    pub sessions: u32,
+    pub focus: Duration
+}
+
+/// Path of the statistics file, $XDG_DATA_HOME/dinfo/pomodoro.ini
+pub fn stats_path() -> PathBuf {
+    glib::user_data_dir().join("dinfo").join("pomodoro.ini")
+}
+
+fn load_file(path: &Path) -> KeyFile {
+    let file = KeyFile::new();
+
+    if !path.exists() {
+        return file;
+    }
+
+    if let Err(e) = file.load_from_file(path, KeyFileFlags::KEEP_COMMENTS) {
+        eprintln!("Failed to load pomodoro statistics from {}: {e}", path.display());
+    }
+
+    file
+}
+
+/// The statistics of a day, zero if nothing was done that day
+pub fn day(date: NaiveDate) -> DayStats {
+    day_in(&stats_path(), date)
+}
+
+fn day_in(path: &Path, date: NaiveDate) -> DayStats {
+    let file = load_file(path);
+    let group = date.to_string();
+
+    DayStats {
+        sessions: file.integer(&group, "sessions").unwrap_or(0).max(0) as u32,
+        focus: Duration::from_secs(file.uint64(&group, "focus-minutes").unwrap_or(0) * 60)
+    }
+}
+
+/// Counts a finished work session on the day
+pub fn add_session(date: NaiveDate, length: Duration) -> Result<DayStats, Box<dyn Error>> {
+    add_session_to(&stats_path(), date, length)
+}
+
+fn add_session_to(path: &Path, date: NaiveDate, length: Duration) -> Result<DayStats, Box<dyn Error>> {
+    let file = load_file(path);
+    let group = date.to_string();
+
+    let sessions = file.integer(&group, "sessions").unwrap_or(0).max(0) as u32 + 1;
+    let focus_minutes = file.uint64(&group, "focus-minutes").unwrap_or(0) + length.as_secs() / 60;
+
+    file.set_integer(&group, "sessions", sessions as i32);
+    file.set_uint64(&group, "focus-minutes", focus_minutes);
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    file.save_to_file(path)?;
+
+    Ok(DayStats { sessions, focus: Duration::from_secs(focus_minutes * 60) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A statistics file in a new temporary directory, removed when it's dropped
+    struct TempStats(PathBuf);
+
+    impl TempStats {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("dinfo-pomodoro-{}-{name}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            TempStats(dir.join("dinfo").join("pomodoro.ini"))
+        }
+    }
+
+    impl Drop for TempStats {
+        fn drop(&mut self) {
+            if let Some(dir) = self.0.parent().and_then(Path::parent) {
+                let _ = std::fs::remove_dir_all(dir);
+            }
+        }
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn missing_file_is_zero() {
+        let stats = TempStats::new("missing");
+        let day = day_in(&stats.0, date(2026, 10, 19));
+        assert_eq!(day.sessions, 0);
+        assert_eq!(day.focus, Duration::ZERO);
+    }
+
+    #[test]
+    fn sessions_round_trip() {
+        let stats = TempStats::new("round-trip");
+        let today = date(2026, 10, 19);
+
+        let first = add_session_to(&stats.0, today, Duration::from_secs(25 * 60)).unwrap();
+        assert_eq!(first.sessions, 1);
+        assert_eq!(first.focus, Duration::from_secs(25 * 60));
+
+        // Only whole minutes are counted
+        let second = add_session_to(&stats.0, today, Duration::from_secs(50 * 60 + 30)).unwrap();
+        assert_eq!(second.sessions, 2);
+        assert_eq!(second.focus, Duration::from_secs(75 * 60));
+
+        add_session_to(&stats.0, date(2026, 10, 20), Duration::from_secs(25 * 60)).unwrap();
+
+        let read = day_in(&stats.0, today);
+        assert_eq!(read.sessions, 2);
+        assert_eq!(read.focus, Duration::from_secs(75 * 60));
+        assert_eq!(day_in(&stats.0, date(2026, 10, 20)).sessions, 1);
+        assert_eq!(day_in(&stats.0, date(2026, 10, 18)).sessions, 0);
+
+        let text = std::fs::read_to_string(&stats.0).unwrap();
+        assert!(text.contains("[2026-10-19]\nsessions=2\nfocus-minutes=75\n"), "{text}");
+    }
+}
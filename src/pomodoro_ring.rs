@@ -0,0 +1,172 @@
+use gtk::{ Align, Box as Gbox, DrawingArea, GestureClick, Label, Overlay, cairo, gdk, glib, prelude::* };
+use chrono::Local;
+
+use std::f64::consts::PI;
+use std::rc::Rc;
+
+use crate::clock::Clock;
+use crate::pomodoro::{ Phase, Pomodoro };
+use crate::timers::{ format_duration, format_duration_words };
+
+// The ring fills up clockwise from the top as the phase goes on. It's drawn with the css color
+// of the widget, so the phases can be colored with the .pomodoro-work, .pomodoro-short-break, ... classes.
+
+const RING_WIDTH: f64 = 8.0;
+
+fn draw_ring(area: &DrawingArea, cr: &cairo::Context, width: i32, height: i32, progress: f64) {
+    let color = area.color();
+    let center = (width as f64 / 2.0, height as f64 / 2.0);
+    let radius = (width.min(height) as f64 / 2.0) - RING_WIDTH;
+    let start = -PI / 2.0;
+
+    cr.set_line_width(RING_WIDTH);
+    cr.set_line_cap(cairo::LineCap::Round);
+
+    // The track
+    cr.set_source_rgba(color.red() as f64, color.green() as f64, color.blue() as f64, color.alpha() as f64 * 0.2);
+    cr.arc(center.0, center.1, radius, 0.0, 2.0 * PI);
+    let _ = cr.stroke();
+
+    if progress > 0.0 {
+        cr.set_source_rgba(color.red() as f64, color.green() as f64, color.blue() as f64, color.alpha() as f64);
+        cr.arc(center.0, center.1, radius, start, start + progress.clamp(0.0, 1.0) * 2.0 * PI);
+        let _ = cr.stroke();
+    }
+}
+
+/// Builds the pomodoro's progress ring with the phase and the time left in the middle.
+/// Left click starts or pauses it, right click skips the phase, middle click resets it
+pub fn build_pomodoro(pomodoro: &Pomodoro, clock_state: &Clock) -> Gbox {
+    let container = {
+        Gbox::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .name("pomodoro")
+        .css_classes(["pomodoro"])
+        .halign(Align::Center)
+        .build()
+    };
+
+    let ring = {
+        DrawingArea::builder()
+        .content_width(140)
+        .content_height(140)
+        .css_classes(["text", "pomodoro_ring"])
+        .build()
+    };
+
+    let phase = {
+        Label::builder()
+        .css_classes(["text", "pomodoro_phase"])
+        .build()
+    };
+
+    let remaining = {
+        Label::builder()
+        .css_classes(["text", "pomodoro_remaining"])
+        .build()
+    };
+
+    let cycles = {
+        Label::builder()
+        .css_classes(["text", "pomodoro_cycles"])
+        .build()
+    };
+
+    let labels = {
+        Gbox::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .halign(Align::Center)
+        .valign(Align::Center)
+        .build()
+    };
+    labels.append(&phase);
+    labels.append(&remaining);
+    labels.append(&cycles);
+
+    let overlay = Overlay::builder().child(&ring).build();
+    overlay.add_overlay(&labels);
+    container.append(&overlay);
+
+    ring.set_draw_func(glib::clone!(
+        #[weak]
+        pomodoro,
+        move |area, cr, width, height| {
+            draw_ring(area, cr, width, height, pomodoro.session().progress(Local::now()));
+        }
+    ));
+
+    let update_ui = Rc::new(glib::clone!(
+        #[weak]
+        container,
+        #[weak]
+        pomodoro,
+        #[weak]
+        ring,
+        move || {
+            let session = pomodoro.session();
+            let config = &crate::CONFIG.get().unwrap().pomodoro;
+            let now = Local::now();
+
+            phase.set_label(&match session.is_paused() {
+                true => format!("{} (paused)", session.phase),
+                false => session.phase.to_string()
+            });
+            remaining.set_label(&match session.phase {
+                Phase::Idle => format_duration(config.work),
+                _ => format_duration(session.remaining(now))
+            });
+
+            let done = pomodoro.cycle().min(config.cycles) as usize;
+            cycles.set_label(&format!("{}{}", "●".repeat(done), "○".repeat(config.cycles as usize - done)));
+
+            for class in Phase::ALL_CSS_CLASSES {
+                container.remove_css_class(class);
+            }
+            container.add_css_class(session.phase.css_class());
+
+            let today = pomodoro.today();
+            container.set_tooltip_text(Some(&format!(
+                "Today: {} sessions, {} of focus\nLeft click to {}, right click to skip, middle click to reset",
+                today.sessions,
+                format_duration_words(today.focus),
+                match (session.phase, session.is_running()) {
+                    (Phase::Idle, _) => "start",
+                    (_, true) => "pause",
+                    (_, false) => "continue"
+                }
+            )));
+
+            ring.queue_draw();
+        }
+    ));
+
+    update_ui();
+
+    pomodoro.connect_changed(glib::clone!(
+        #[strong]
+        update_ui,
+        move |_| update_ui()
+    ));
+    clock_state.connect_tick(glib::clone!(
+        #[strong]
+        update_ui,
+        move |_| update_ui()
+    ));
+
+    let click = GestureClick::builder().button(0).build();
+    click.connect_pressed(glib::clone!(
+        #[weak]
+        pomodoro,
+        move |gesture, _, _, _| {
+            match gesture.current_button() {
+                gdk::BUTTON_PRIMARY => pomodoro.toggle(),
+                gdk::BUTTON_SECONDARY => pomodoro.skip(),
+                gdk::BUTTON_MIDDLE => pomodoro.reset(),
+                _ => {}
+            }
+        }
+    ));
+    container.add_controller(click);
+
+    container
+}
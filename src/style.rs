@@ -0,0 +1,128 @@
+use gtk::{ CssProvider, gdk::Display, gio, glib, prelude::* };
+
+use std::cell::RefCell;
+use std::path::PathBuf;
+
+use crate::{ config, theme };
+
+// The built-in style.css is loaded first, then the theme palette's variables (see the theme module),
+// then the user's style.css on top of them with a higher priority, so every rule can be overridden without rebuilding.
+// The user file is watched, and the provider is swapped out for a new one when it changes.
+
+/// Priority of the theme palette's variables, above the built-in defaults
+pub const THEME_CSS_PRIORITY: u32 = gtk::STYLE_PROVIDER_PRIORITY_USER + 1;
+/// Priority of the colors generated from the wallpaper, above the theme palette
+pub const WALLPAPER_CSS_PRIORITY: u32 = gtk::STYLE_PROVIDER_PRIORITY_USER + 2;
+/// Priority of the user's style.css, above everything else
+const USER_CSS_PRIORITY: u32 = gtk::STYLE_PROVIDER_PRIORITY_USER + 3;
+
+thread_local! {
+    static USER_PROVIDER: RefCell<Option<CssProvider>> = const { RefCell::new(None) };
+    // Needs to be kept alive, the monitor stops when it's dropped
+    static USER_MONITOR: RefCell<Option<gio::FileMonitor>> = const { RefCell::new(None) };
+}
+
+/// Path of the user's css file, $XDG_CONFIG_HOME/dinfo/style.css
+pub fn user_css_path() -> PathBuf {
+    config::config_dir().join("style.css")
+}
+
+/// Creates a provider that reports parse errors with the file name and location
+pub fn new_provider(name: String) -> CssProvider {
+    let provider = CssProvider::new();
+
+    provider.connect_parsing_error(move |_, section, error| {
+        let location = section.start_location();
+        eprintln!(
+            "CSS error in {} at line {}, character {}: {}",
+            name,
+            location.lines() + 1,
+            location.line_chars() + 1,
+            error
+        );
+    });
+
+    provider
+}
+
+/// Loads the user's css file (if it exists) into a new provider, and replaces the previous one with it
+fn load_user_css(display: &Display) {
+    let path = user_css_path();
+
+    let provider = if path.exists() {
+        match std::fs::read_to_string(&path) {
+            Ok(css) => {
+                let provider = new_provider(path.display().to_string());
+                provider.load_from_string(&css);
+                println!("Loaded user css from {}", path.display());
+                Some(provider)
+            },
+            Err(e) => {
+                eprintln!("Failed to read user css from {}: {e}", path.display());
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    if let Some(provider) = &provider {
+        gtk::style_context_add_provider_for_display(display, provider, USER_CSS_PRIORITY);
+    }
+
+    let old = USER_PROVIDER.with(|p| p.replace(provider));
+    if let Some(old) = old {
+        gtk::style_context_remove_provider_for_display(display, &old);
+    }
+}
+
+/// Reloads the user's css every time the file is saved, created or removed
+fn watch_user_css(display: &Display) {
+    let file = gio::File::for_path(user_css_path());
+
+    let monitor = match file.monitor_file(gio::FileMonitorFlags::WATCH_MOVES, gio::Cancellable::NONE) {
+        Ok(m) => m,
+        Err(e) => {
+            eprintln!("Failed to watch user css for changes: {e}");
+            return;
+        }
+    };
+
+    monitor.connect_changed(glib::clone!(
+        #[strong]
+        display,
+        move |_, _, _, event| {
+            match event {
+                gio::FileMonitorEvent::ChangesDoneHint |
+                gio::FileMonitorEvent::Created |
+                gio::FileMonitorEvent::Deleted |
+                gio::FileMonitorEvent::MovedIn |
+                gio::FileMonitorEvent::MovedOut |
+                gio::FileMonitorEvent::Renamed => load_user_css(&display),
+                _ => {}
+            }
+        }
+    ));
+
+    USER_MONITOR.with(|m| m.replace(Some(monitor)));
+}
+
+pub fn load_css() {
+    let display = Display::default().expect("Could not connect to a display.");
+
+    // Load the built-in css
+    let provider = new_provider("built-in style.css".to_string());
+    provider.load_from_string(include_str!("style.css"));
+
+    // Add the provider to the default screen
+    gtk::style_context_add_provider_for_display(
+        &display,
+        &provider,
+        gtk::STYLE_PROVIDER_PRIORITY_USER,
+    );
+
+    theme::load_theme(&display);
+
+    load_user_css(&display);
+    watch_user_css(&display);
+}
@@ -0,0 +1,188 @@
+use gtk::glib;
+use zbus::{ CacheProperties, Connection, dbus_proxy };
+
+use futures::{ future, stream::StreamExt };
+use std::pin::pin;
+use std::time::Duration;
+
+use crate::clock::Clock;
+
+// The clock's timers sleep on the wall clock, but they dont notice when the time zone changes,
+// and some things (like the analog clock) only redraw every minute. So the system bus is watched for:
+// - logind's PrepareForSleep signal, which is sent with false when the system wakes up
+//   https://www.freedesktop.org/software/systemd/man/latest/org.freedesktop.login1.html
+// - timedated's Timezone property
+//   https://www.freedesktop.org/software/systemd/man/latest/org.freedesktop.timedate1.html
+//
+// timedated is also asked if the clock is synchronized. It doesnt send a signal when NTPSynchronized changes
+// (and it exits when it's idle), so it's polled.
+
+/// chrono rechecks /etc/localtime at most once a second, the refresh waits until it sees the new zone
+const TIMEZONE_CACHE_DELAY: Duration = Duration::from_millis(1100);
+
+/// How often timedated is asked if the clock is synchronized
+const SYNC_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+#[dbus_proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+trait Login1Manager {
+    /// PrepareForSleep signal, start is true before the suspend and false after waking up
+    #[dbus_proxy(signal)]
+    fn prepare_for_sleep(&self, start: bool) -> zbus::Result<()>;
+}
+
+#[dbus_proxy(
+    interface = "org.freedesktop.timedate1",
+    default_service = "org.freedesktop.timedate1",
+    default_path = "/org/freedesktop/timedate1"
+)]
+trait Timedate1 {
+    /// The system time zone, like Europe/Budapest
+    #[dbus_proxy(property)]
+    fn timezone(&self) -> zbus::Result<String>;
+
+    /// The kernel says the clock is synchronized with a time server
+    #[dbus_proxy(property, name = "NTPSynchronized")]
+    fn ntp_synchronized(&self) -> zbus::Result<bool>;
+
+    /// A time synchronization service is turned on
+    #[dbus_proxy(property, name = "NTP")]
+    fn ntp(&self) -> zbus::Result<bool>;
+}
+
+/// Whether the system clock can be trusted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncStatus {
+    pub synced: bool,
+    pub ntp_enabled: bool
+}
+
+/// Asks timedated if the system clock is synchronized
+pub async fn read_sync_status(connection: &Connection) -> zbus::Result<SyncStatus> {
+    // The values are read every time, cached ones would never change
+    let proxy = Timedate1Proxy::builder(connection)
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+
+    Ok(SyncStatus { synced: proxy.ntp_synchronized().await?, ntp_enabled: proxy.ntp().await? })
+}
+
+/// Polls the synchronization status into the clock's properties, and right after the time changed
+async fn poll_sync_status(connection: &Connection, clock_state: &Clock) {
+    let (wake_snd, wake_rcv) = async_channel::bounded::<()>(1);
+    clock_state.connect_time_changed(move |_| {
+        let _ = wake_snd.try_send(());
+    });
+
+    // Only the first failure is printed, timedated might not exist at all
+    let mut failing = false;
+
+    loop {
+        match read_sync_status(connection).await {
+            Ok(status) => {
+                failing = false;
+                if clock_state.sync_known() && status.synced != clock_state.synced() {
+                    println!("System clock synchronized: {}", status.synced);
+                }
+                clock_state.set_synced(status.synced);
+                clock_state.set_ntp_enabled(status.ntp_enabled);
+                clock_state.set_sync_known(true);
+            },
+            Err(e) => {
+                if !failing {
+                    eprintln!("Failed to ask timedated if the clock is synchronized: {e}");
+                    failing = true;
+                }
+                clock_state.set_sync_known(false);
+            }
+        }
+
+        future::select(pin!(glib::timeout_future(SYNC_POLL_INTERVAL)), pin!(wake_rcv.recv())).await;
+    }
+}
+
+/// Calls `on_resume` every time the system wakes up from a suspend.
+/// Returns when the signal stream ends, or if logind cant be reached
+pub async fn watch_resume<F: Fn()>(connection: &Connection, on_resume: F) -> zbus::Result<()> {
+    let proxy = Login1ManagerProxy::new(connection).await?;
+    let mut signals = proxy.receive_prepare_for_sleep().await?;
+
+    while let Some(signal) = signals.next().await {
+        match signal.args() {
+            Ok(args) if !args.start => on_resume(),
+            Ok(_) => {},
+            Err(e) => eprintln!("Failed to parse PrepareForSleep signal from logind: {e}")
+        }
+    }
+
+    Ok(())
+}
+
+/// Calls `on_change` with the new time zone every time it changes.
+/// Returns when the property stream ends, or if timedated cant be reached
+pub async fn watch_timezone<F: Fn(&str)>(connection: &Connection, on_change: F) -> zbus::Result<()> {
+    let proxy = Timedate1Proxy::new(connection).await?;
+    let mut changes = proxy.receive_timezone_changed().await;
+
+    // The stream might start with the current value, that's not a change
+    let mut current = proxy.timezone().await?;
+
+    while let Some(change) = changes.next().await {
+        let timezone = match change.get().await {
+            Ok(t) => t,
+            Err(e) => {
+                eprintln!("Failed to read the time zone from timedated: {e}");
+                continue;
+            }
+        };
+
+        if timezone != current {
+            on_change(&timezone);
+            current = timezone;
+        }
+    }
+
+    Ok(())
+}
+
+/// Refreshes the clock and the date right away when the system wakes up or the time zone changes,
+/// and keeps the clock's sync properties up to date
+pub fn watch(clock_state: &Clock) {
+    glib::spawn_future_local(glib::clone!(
+        #[weak]
+        clock_state,
+        async move {
+            let connection = match Connection::system().await {
+                Ok(c) => c,
+                Err(e) => {
+                    eprintln!("Failed to connect to system DBus, the clock wont refresh after a suspend or a time zone change: {e}");
+                    return;
+                }
+            };
+
+            let resume = watch_resume(&connection, || clock_state.time_changed());
+            let timezone = watch_timezone(&connection, |timezone| {
+                println!("Time zone changed to {timezone}");
+                glib::timeout_add_local_once(TIMEZONE_CACHE_DELAY, glib::clone!(
+                    #[weak]
+                    clock_state,
+                    move || clock_state.time_changed()
+                ));
+            });
+
+            let sync_status = poll_sync_status(&connection, &clock_state);
+
+            let (resume, timezone, _) = future::join3(resume, timezone, sync_status).await;
+            if let Err(e) = resume {
+                eprintln!("Failed to watch logind for resumes from suspend: {e}");
+            }
+            if let Err(e) = timezone {
+                eprintln!("Failed to watch timedated for time zone changes: {e}");
+            }
+        }
+    ));
+}
@@ -0,0 +1,152 @@
+use gtk::{ CssProvider, gdk::Display, gio, glib, prelude::* };
+use zbus::Connection;
+
+use std::cell::{ Cell, RefCell };
+
+mod palette;
+pub mod portal;
+mod wallpaper;
+pub use palette::{ Palette, PALETTES };
+use portal::ColorScheme;
+use wallpaper::WallpaperColors;
+
+use crate::{ config::expand_home, style };
+
+// The palette's colors are css variables (--dinfo-text, --dinfo-island-border, ...) that style.css uses.
+// The palette is picked from the config's [theme] dark and light keys, following the desktop's color scheme.
+// If a pywal/matugen color file exists, its colors override the palette's text, outline and island colors.
+
+thread_local! {
+    static THEME_PROVIDER: RefCell<Option<CssProvider>> = const { RefCell::new(None) };
+    static WALLPAPER_PROVIDER: RefCell<Option<CssProvider>> = const { RefCell::new(None) };
+    // Needs to be kept alive, the monitor stops when it's dropped
+    static WALLPAPER_MONITOR: RefCell<Option<gio::FileMonitor>> = const { RefCell::new(None) };
+    static COLOR_SCHEME: Cell<ColorScheme> = const { Cell::new(ColorScheme::NoPreference) };
+}
+
+/// Returns the palette configured for the color scheme, falling back to Catppuccin Mocha if the name is unknown
+pub fn palette_for(scheme: ColorScheme) -> &'static Palette {
+    let config = &crate::CONFIG.get().unwrap().theme;
+    let name = match scheme {
+        ColorScheme::Light => &config.light,
+        ColorScheme::Dark | ColorScheme::NoPreference => &config.dark
+    };
+
+    match Palette::by_name(name) {
+        Some(p) => p,
+        None => {
+            eprintln!(
+                "Unknown theme palette '{name}', falling back to catppuccin-mocha. Available palettes: {}",
+                PALETTES.iter().map(|p| p.name).collect::<Vec<_>>().join(", ")
+            );
+            &palette::CATPPUCCIN_MOCHA
+        }
+    }
+}
+
+/// Replaces the current palette's css variables with the new palette's
+pub fn apply_palette(palette: &Palette) {
+    THEME_PROVIDER.with(|provider| {
+        if let Some(provider) = provider.borrow().as_ref() {
+            provider.load_from_string(&palette.to_css());
+        }
+    });
+}
+
+/// Switches the palette and the wallpaper colors to the color scheme
+fn apply_color_scheme(scheme: ColorScheme) {
+    COLOR_SCHEME.set(scheme);
+    apply_palette(palette_for(scheme));
+    load_wallpaper_colors();
+}
+
+/// (Re)generates the wallpaper colors from the configured color file.
+/// If the file doesnt exist the palette's colors are used
+fn load_wallpaper_colors() {
+    let Some(path) = &crate::CONFIG.get().unwrap().theme.wallpaper else {
+        return;
+    };
+    let path = expand_home(path);
+
+    let css = if path.exists() {
+        match WallpaperColors::from_file(&path, COLOR_SCHEME.get()) {
+            Ok(colors) => colors.to_css(),
+            Err(e) => {
+                eprintln!("Failed to read wallpaper colors from {}: {e}", path.display());
+                String::new()
+            }
+        }
+    } else {
+        String::new()
+    };
+
+    WALLPAPER_PROVIDER.with(|provider| {
+        if let Some(provider) = provider.borrow().as_ref() {
+            provider.load_from_string(&css);
+        }
+    });
+}
+
+/// Regenerates the wallpaper colors every time the color file is rewritten
+fn watch_wallpaper_colors() {
+    let Some(path) = &crate::CONFIG.get().unwrap().theme.wallpaper else {
+        return;
+    };
+    let file = gio::File::for_path(expand_home(path));
+
+    let monitor = match file.monitor_file(gio::FileMonitorFlags::WATCH_MOVES, gio::Cancellable::NONE) {
+        Ok(m) => m,
+        Err(e) => {
+            eprintln!("Failed to watch wallpaper colors for changes: {e}");
+            return;
+        }
+    };
+
+    monitor.connect_changed(|_, _, _, event| {
+        match event {
+            gio::FileMonitorEvent::ChangesDoneHint |
+            gio::FileMonitorEvent::Created |
+            gio::FileMonitorEvent::Deleted |
+            gio::FileMonitorEvent::MovedIn |
+            gio::FileMonitorEvent::MovedOut |
+            gio::FileMonitorEvent::Renamed => load_wallpaper_colors(),
+            _ => {}
+        }
+    });
+
+    WALLPAPER_MONITOR.with(|m| m.replace(Some(monitor)));
+}
+
+/// Loads the dark palette, then switches between the dark and light palette following the desktop
+pub fn load_theme(display: &Display) {
+    let provider = style::new_provider("theme palette".to_string());
+    gtk::style_context_add_provider_for_display(display, &provider, style::THEME_CSS_PRIORITY);
+    THEME_PROVIDER.with(|p| p.replace(Some(provider)));
+
+    let provider = style::new_provider("wallpaper colors".to_string());
+    gtk::style_context_add_provider_for_display(display, &provider, style::WALLPAPER_CSS_PRIORITY);
+    WALLPAPER_PROVIDER.with(|p| p.replace(Some(provider)));
+
+    apply_color_scheme(ColorScheme::NoPreference);
+    watch_wallpaper_colors();
+
+    glib::spawn_future_local(async move {
+        let connection = match Connection::session().await {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("Failed to connect to session DBus, the theme will not follow the desktop's color scheme: {e}");
+                return;
+            }
+        };
+
+        let watch = portal::watch_color_scheme(&connection, portal::PORTAL_DESTINATION, |scheme| {
+            if scheme != COLOR_SCHEME.get() {
+                apply_color_scheme(scheme);
+            }
+        });
+
+        if let Err(e) = watch.await {
+            eprintln!("Failed to follow the color scheme from the settings portal: {e}");
+        }
+    });
+}
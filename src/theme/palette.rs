@@ -0,0 +1,234 @@
+/// Colors of a theme, each one is mapped to a css variable (see Palette::to_css)
+#[derive(Debug, Clone)]
+pub struct Palette {
+    pub name: &'static str,
+    /// Color of the text, and the clock
+    pub text: &'static str,
+    /// Outline around the text
+    pub outline: &'static str,
+    pub island_background: &'static str,
+    pub island_border: &'static str,
+    /// Island background at night
+    pub night_background: &'static str,
+    // Island border colors for the weather conditions
+    pub clear: &'static str,
+    pub clear_night: &'static str,
+    pub cloudy: &'static str,
+    pub fog: &'static str,
+    pub drizzle: &'static str,
+    pub rain: &'static str,
+    pub snow: &'static str,
+    pub thunderstorm: &'static str,
+    // Clock colors for the temperature bands
+    pub hot: &'static str,
+    pub freezing: &'static str
+}
+
+impl Palette {
+    /// Css defining the palette's variables. They are set on the windows, so every widget inherits them
+    pub fn to_css(&self) -> String {
+        format!(
+"window {{
+    --dinfo-text: {};
+    --dinfo-outline: {};
+    --dinfo-island-background: {};
+    --dinfo-island-border: {};
+    --dinfo-night-background: {};
+    --dinfo-clear: {};
+    --dinfo-clear-night: {};
+    --dinfo-cloudy: {};
+    --dinfo-fog: {};
+    --dinfo-drizzle: {};
+    --dinfo-rain: {};
+    --dinfo-snow: {};
+    --dinfo-thunderstorm: {};
+    --dinfo-hot: {};
+    --dinfo-freezing: {};
+}}",
+            self.text,
+            self.outline,
+            self.island_background,
+            self.island_border,
+            self.night_background,
+            self.clear,
+            self.clear_night,
+            self.cloudy,
+            self.fog,
+            self.drizzle,
+            self.rain,
+            self.snow,
+            self.thunderstorm,
+            self.hot,
+            self.freezing
+        )
+    }
+
+    /// Finds a bundled palette by its name
+    pub fn by_name(name: &str) -> Option<&'static Palette> {
+        PALETTES.iter().find(|p| p.name == name)
+    }
+}
+
+pub const CATPPUCCIN_LATTE: Palette = Palette {
+    name: "catppuccin-latte",
+    text: "#fe640b",
+    outline: "#ccd0da",
+    island_background: "#ccd0da7d",
+    island_border: "#1e66f5",
+    night_background: "#eff1f57d",
+    clear: "#df8e1d",
+    clear_night: "#7287fd",
+    cloudy: "#7c7f93",
+    fog: "#8c8fa1",
+    drizzle: "#04a5e5",
+    rain: "#209fb5",
+    snow: "#4c4f69",
+    thunderstorm: "#8839ef",
+    hot: "#d20f39",
+    freezing: "#7287fd"
+};
+
+pub const CATPPUCCIN_FRAPPE: Palette = Palette {
+    name: "catppuccin-frappe",
+    text: "#ef9f76",
+    outline: "#414559",
+    island_background: "#4145597d",
+    island_border: "#8caaee",
+    night_background: "#3034467d",
+    clear: "#e5c890",
+    clear_night: "#babbf1",
+    cloudy: "#949cbb",
+    fog: "#838ba7",
+    drizzle: "#99d1db",
+    rain: "#85c1dc",
+    snow: "#c6d0f5",
+    thunderstorm: "#ca9ee6",
+    hot: "#e78284",
+    freezing: "#babbf1"
+};
+
+pub const CATPPUCCIN_MACCHIATO: Palette = Palette {
+    name: "catppuccin-macchiato",
+    text: "#f5a97f",
+    outline: "#363a4f",
+    island_background: "#363a4f7d",
+    island_border: "#8aadf4",
+    night_background: "#24273a7d",
+    clear: "#eed49f",
+    clear_night: "#b7bdf8",
+    cloudy: "#939ab7",
+    fog: "#8087a2",
+    drizzle: "#91d7e3",
+    rain: "#7dc4e4",
+    snow: "#cad3f5",
+    thunderstorm: "#c6a0f6",
+    hot: "#ed8796",
+    freezing: "#b7bdf8"
+};
+
+pub const CATPPUCCIN_MOCHA: Palette = Palette {
+    name: "catppuccin-mocha",
+    text: "#fab387",
+    outline: "#313244",
+    island_background: "#3132447d",
+    island_border: "#89b4fa",
+    night_background: "#1e1e2e7d",
+    clear: "#f9e2af",
+    clear_night: "#b4befe",
+    cloudy: "#9399b2",
+    fog: "#7f849c",
+    drizzle: "#89dceb",
+    rain: "#74c7ec",
+    snow: "#cdd6f4",
+    thunderstorm: "#cba6f7",
+    hot: "#f38ba8",
+    freezing: "#b4befe"
+};
+
+pub const GRUVBOX_DARK: Palette = Palette {
+    name: "gruvbox-dark",
+    text: "#fe8019",
+    outline: "#3c3836",
+    island_background: "#3c38367d",
+    island_border: "#83a598",
+    night_background: "#1d20217d",
+    clear: "#fabd2f",
+    clear_night: "#d3869b",
+    cloudy: "#a89984",
+    fog: "#928374",
+    drizzle: "#8ec07c",
+    rain: "#83a598",
+    snow: "#ebdbb2",
+    thunderstorm: "#d3869b",
+    hot: "#fb4934",
+    freezing: "#83a598"
+};
+
+pub const GRUVBOX_LIGHT: Palette = Palette {
+    name: "gruvbox-light",
+    text: "#af3a03",
+    outline: "#ebdbb2",
+    island_background: "#ebdbb27d",
+    island_border: "#076678",
+    night_background: "#f9f5d77d",
+    clear: "#b57614",
+    clear_night: "#8f3f71",
+    cloudy: "#7c6f64",
+    fog: "#928374",
+    drizzle: "#427b58",
+    rain: "#076678",
+    snow: "#3c3836",
+    thunderstorm: "#8f3f71",
+    hot: "#9d0006",
+    freezing: "#076678"
+};
+
+pub const NORD: Palette = Palette {
+    name: "nord",
+    text: "#88c0d0",
+    outline: "#3b4252",
+    island_background: "#3b42527d",
+    island_border: "#81a1c1",
+    night_background: "#2e34407d",
+    clear: "#ebcb8b",
+    clear_night: "#b48ead",
+    cloudy: "#4c566a",
+    fog: "#616e88",
+    drizzle: "#8fbcbb",
+    rain: "#5e81ac",
+    snow: "#eceff4",
+    thunderstorm: "#b48ead",
+    hot: "#bf616a",
+    freezing: "#8fbcbb"
+};
+
+pub const NORD_LIGHT: Palette = Palette {
+    name: "nord-light",
+    text: "#5e81ac",
+    outline: "#eceff4",
+    island_background: "#e5e9f07d",
+    island_border: "#81a1c1",
+    night_background: "#d8dee97d",
+    clear: "#d08770",
+    clear_night: "#b48ead",
+    cloudy: "#4c566a",
+    fog: "#616e88",
+    drizzle: "#8fbcbb",
+    rain: "#5e81ac",
+    snow: "#2e3440",
+    thunderstorm: "#b48ead",
+    hot: "#bf616a",
+    freezing: "#88c0d0"
+};
+
+/// Every bundled palette
+pub static PALETTES: [Palette; 8] = [
+    CATPPUCCIN_LATTE,
+    CATPPUCCIN_FRAPPE,
+    CATPPUCCIN_MACCHIATO,
+    CATPPUCCIN_MOCHA,
+    GRUVBOX_DARK,
+    GRUVBOX_LIGHT,
+    NORD,
+    NORD_LIGHT
+];
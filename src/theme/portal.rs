@@ -0,0 +1,202 @@
+use zbus::{ Connection, dbus_proxy, zvariant::{ OwnedValue, Value } };
+
+use futures::stream::StreamExt;
+
+// The desktop's light/dark preference is read from the XDG settings portal:
+// org.freedesktop.appearance color-scheme, 0 = no preference, 1 = dark, 2 = light
+// https://flatpak.github.io/xdg-desktop-portal/docs/doc-org.freedesktop.portal.Settings.html
+//
+// The bus name is passed in, so it can be pointed to a mock portal on the session bus.
+
+/// Bus name of the real settings portal
+pub const PORTAL_DESTINATION: &str = "org.freedesktop.portal.Desktop";
+
+const APPEARANCE_NAMESPACE: &str = "org.freedesktop.appearance";
+const COLOR_SCHEME_KEY: &str = "color-scheme";
+
+#[dbus_proxy(
+    interface = "org.freedesktop.portal.Settings",
+    default_service = "org.freedesktop.portal.Desktop",
+    default_path = "/org/freedesktop/portal/desktop"
+)]
+trait Settings {
+    /// ReadOne method, since version 2 of the portal
+    fn read_one(&self, namespace: &str, key: &str) -> zbus::Result<OwnedValue>;
+
+    /// Read method, deprecated, but older portals only have this. The value is wrapped in an extra variant
+    fn read(&self, namespace: &str, key: &str) -> zbus::Result<OwnedValue>;
+
+    /// SettingChanged signal
+    #[dbus_proxy(signal)]
+    fn setting_changed(&self, namespace: &str, key: &str, value: Value<'_>) -> zbus::Result<()>;
+}
+
+/// The color scheme the desktop prefers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorScheme {
+    NoPreference,
+    Dark,
+    Light
+}
+
+impl ColorScheme {
+    pub fn from_value(value: &Value<'_>) -> Self {
+        match value {
+            // Read() wraps the value in one more variant
+            Value::Value(inner) => Self::from_value(inner),
+            Value::U32(1) => Self::Dark,
+            Value::U32(2) => Self::Light,
+            _ => Self::NoPreference
+        }
+    }
+}
+
+async fn settings_proxy<'a>(connection: &Connection, destination: &'a str) -> zbus::Result<SettingsProxy<'a>> {
+    SettingsProxy::builder(connection)
+        .destination(destination)?
+        .build()
+        .await
+}
+
+/// Reads the current color scheme from the settings portal at `destination`
+pub async fn read_color_scheme(connection: &Connection, destination: &str) -> zbus::Result<ColorScheme> {
+    let proxy = settings_proxy(connection, destination).await?;
+
+    let value = match proxy.read_one(APPEARANCE_NAMESPACE, COLOR_SCHEME_KEY).await {
+        Ok(v) => v,
+        Err(_) => proxy.read(APPEARANCE_NAMESPACE, COLOR_SCHEME_KEY).await?
+    };
+
+    Ok(ColorScheme::from_value(&value))
+}
+
+/// Calls `on_change` with the current color scheme, then every time it changes.
+/// Returns when the signal stream ends, or if the portal cant be reached
+pub async fn watch_color_scheme<F: Fn(ColorScheme)>(connection: &Connection, destination: &str, on_change: F) -> zbus::Result<()> {
+    let proxy = settings_proxy(connection, destination).await?;
+
+    // Subscribe before reading, so a change between the two isnt missed
+    let mut changes = proxy.receive_setting_changed().await?;
+
+    on_change(read_color_scheme(connection, destination).await?);
+
+    while let Some(signal) = changes.next().await {
+        let args = match signal.args() {
+            Ok(a) => a,
+            Err(e) => {
+                eprintln!("Failed to parse SettingChanged signal from the settings portal: {e}");
+                continue;
+            }
+        };
+
+        if args.namespace() == &APPEARANCE_NAMESPACE && args.key() == &COLOR_SCHEME_KEY {
+            on_change(ColorScheme::from_value(args.value()));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use zbus::{ ConnectionBuilder, SignalContext, dbus_interface, zvariant::OwnedValue };
+
+    use futures::{ executor::block_on, future };
+    use std::io::{ BufRead, BufReader };
+    use std::pin::pin;
+    use std::process::{ Child, Command, Stdio };
+
+    const MOCK_DESTINATION: &str = "org.dinfo.MockPortal";
+    const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+
+    /// A private session bus, killed when it's dropped
+    struct PrivateBus {
+        daemon: Child,
+        address: String
+    }
+
+    impl PrivateBus {
+        /// None if dbus-daemon isnt installed
+        fn start() -> Option<Self> {
+            let mut daemon = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .spawn()
+                .ok()?;
+
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take()?).read_line(&mut address).ok()?;
+
+            Some(PrivateBus { daemon, address: address.trim().to_string() })
+        }
+    }
+
+    impl Drop for PrivateBus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+
+    struct MockSettings {
+        color_scheme: u32
+    }
+
+    #[dbus_interface(name = "org.freedesktop.portal.Settings")]
+    impl MockSettings {
+        fn read_one(&self, namespace: &str, key: &str) -> zbus::fdo::Result<OwnedValue> {
+            match (namespace, key) {
+                (APPEARANCE_NAMESPACE, COLOR_SCHEME_KEY) => Ok(OwnedValue::from(self.color_scheme)),
+                _ => Err(zbus::fdo::Error::Failed(format!("No such setting {namespace} {key}")))
+            }
+        }
+
+        #[dbus_interface(signal)]
+        async fn setting_changed(ctxt: &SignalContext<'_>, namespace: &str, key: &str, value: Value<'_>) -> zbus::Result<()>;
+    }
+
+    #[test]
+    fn color_scheme_from_mock_portal() {
+        let Some(bus) = PrivateBus::start() else {
+            eprintln!("dbus-daemon isnt installed, skipping the portal test");
+            return;
+        };
+
+        block_on(async {
+            let portal = ConnectionBuilder::address(bus.address.as_str()).unwrap()
+                .name(MOCK_DESTINATION).unwrap()
+                .serve_at(PORTAL_PATH, MockSettings { color_scheme: 1 }).unwrap()
+                .build()
+                .await
+                .unwrap();
+            let connection = ConnectionBuilder::address(bus.address.as_str()).unwrap().build().await.unwrap();
+
+            let (changes_snd, changes) = async_channel::unbounded();
+            let watch = watch_color_scheme(&connection, MOCK_DESTINATION, move |scheme| {
+                let _ = changes_snd.try_send(scheme);
+            });
+
+            let test = async {
+                // The initial read
+                assert_eq!(changes.recv().await.unwrap(), ColorScheme::Dark);
+
+                let interface = portal.object_server().interface::<_, MockSettings>(PORTAL_PATH).await.unwrap();
+                let ctxt = interface.signal_context();
+
+                // Other settings are ignored
+                MockSettings::setting_changed(ctxt, "org.gnome.desktop.interface", "font-name", Value::from("Sans 11")).await.unwrap();
+                MockSettings::setting_changed(ctxt, APPEARANCE_NAMESPACE, COLOR_SCHEME_KEY, Value::U32(2)).await.unwrap();
+
+                assert_eq!(changes.recv().await.unwrap(), ColorScheme::Light);
+                assert!(changes.is_empty());
+            };
+
+            match future::select(pin!(watch), pin!(test)).await {
+                future::Either::Left((result, _)) => panic!("The watch ended early: {result:?}"),
+                future::Either::Right(_) => {}
+            }
+        });
+    }
+}
@@ -0,0 +1,199 @@
+use serde_json::Value;
+
+use std::path::Path;
+
+use super::portal::ColorScheme;
+
+// Colors generated from the wallpaper by pywal (~/.cache/wal/colors.json) or matugen (a json with material you colors).
+// Only the text, outline and island roles are taken from the wallpaper, the weather colors stay from the palette.
+
+/// Colors taken from the wallpaper
+#[derive(Debug, Clone)]
+pub struct WallpaperColors {
+    pub text: String,
+    pub outline: String,
+    pub island_background: String,
+    pub island_border: String
+}
+
+impl WallpaperColors {
+    /// Reads a pywal or matugen style json. The scheme picks the light or dark colors of matugen files
+    pub fn from_file(path: &Path, scheme: ColorScheme) -> Result<Self, Box<dyn std::error::Error>> {
+        let json: Value = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+
+        if json.get("special").is_some() {
+            Self::from_pywal(&json)
+        } else {
+            Self::from_matugen(&json, scheme)
+        }.ok_or_else(|| format!("{} is not a pywal or matugen color file, or it's missing colors", path.display()).into())
+    }
+
+    /// Pywal: { "special": { "background", "foreground", "cursor" }, "colors": { "color0" .. "color15" } }
+    fn from_pywal(json: &Value) -> Option<Self> {
+        let color = |name: &str| hex_color(json.get("colors")?.get(name)?);
+        let background = hex_color(json.get("special")?.get("background")?)?;
+
+        Some(WallpaperColors {
+            text: color("color1")?,
+            outline: background.clone(),
+            island_background: with_alpha(&background),
+            island_border: color("color4")?
+        })
+    }
+
+    /// Matugen: { "colors": { "dark": { "primary", ... }, "light": { ... } } }
+    /// or the newer { "colors": { "primary": { "dark", "light" }, ... } }
+    fn from_matugen(json: &Value, scheme: ColorScheme) -> Option<Self> {
+        let colors = json.get("colors")?;
+        let scheme = match scheme {
+            ColorScheme::Light => "light",
+            ColorScheme::Dark | ColorScheme::NoPreference => "dark"
+        };
+
+        let color = |name: &str| -> Option<String> {
+            match colors.get(scheme).and_then(|c| c.get(name)) {
+                Some(c) => hex_color(c),
+                None => hex_color(colors.get(name)?.get(scheme)?)
+            }
+        };
+
+        let surface = color("surface_container").or_else(|| color("surface"))?;
+
+        Some(WallpaperColors {
+            text: color("primary")?,
+            outline: color("surface")?,
+            island_background: with_alpha(&surface),
+            island_border: color("secondary")?
+        })
+    }
+
+    /// Css overriding the palette's variables with the wallpaper colors
+    pub fn to_css(&self) -> String {
+        format!(
+"window {{
+    --dinfo-text: {};
+    --dinfo-outline: {};
+    --dinfo-island-background: {};
+    --dinfo-island-border: {};
+}}",
+            self.text,
+            self.outline,
+            self.island_background,
+            self.island_border
+        )
+    }
+}
+
+/// Returns the value if it's a #rrggbb or #rrggbbaa color, so nothing else ends up in the css
+fn hex_color(value: &Value) -> Option<String> {
+    let color = value.as_str()?.trim();
+    let hex = color.strip_prefix('#')?;
+
+    if (hex.len() == 6 || hex.len() == 8) && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        Some(color.to_lowercase())
+    } else {
+        None
+    }
+}
+
+/// Adds the same transparency the islands use in the palettes to a #rrggbb color
+fn with_alpha(color: &str) -> String {
+    match color.len() {
+        7 => format!("{color}7d"),
+        _ => color.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const PYWAL: &str = include_str!("fixtures/pywal.json");
+    const MATUGEN: &str = include_str!("fixtures/matugen.json");
+    const MATUGEN_BY_ROLE: &str = include_str!("fixtures/matugen_by_role.json");
+
+    fn fixture(text: &str) -> Value {
+        serde_json::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn pywal_colors() {
+        let colors = WallpaperColors::from_pywal(&fixture(PYWAL)).unwrap();
+        assert_eq!(colors.text, "#5e7a96");
+        assert_eq!(colors.outline, "#1b1d24");
+        assert_eq!(colors.island_background, "#1b1d247d");
+        assert_eq!(colors.island_border, "#8aa4b8");
+    }
+
+    #[test]
+    fn matugen_colors_by_scheme() {
+        let json = fixture(MATUGEN);
+
+        let dark = WallpaperColors::from_matugen(&json, ColorScheme::Dark).unwrap();
+        assert_eq!(dark.text, "#a6c8ff");
+        assert_eq!(dark.outline, "#111318");
+        assert_eq!(dark.island_background, "#1d20247d");
+        assert_eq!(dark.island_border, "#bdc7dc");
+
+        let light = WallpaperColors::from_matugen(&json, ColorScheme::Light).unwrap();
+        assert_eq!(light.text, "#3b5f90");
+        assert_eq!(light.outline, "#f9f9ff");
+        assert_eq!(light.island_background, "#ededf47d");
+        assert_eq!(light.island_border, "#545f71");
+
+        // No preference is dark
+        assert_eq!(WallpaperColors::from_matugen(&json, ColorScheme::NoPreference).unwrap().text, "#a6c8ff");
+    }
+
+    #[test]
+    fn matugen_colors_by_role() {
+        let json = fixture(MATUGEN_BY_ROLE);
+
+        let dark = WallpaperColors::from_matugen(&json, ColorScheme::Dark).unwrap();
+        assert_eq!(dark.text, "#a6c8ff");
+        // Without surface_container the surface is used
+        assert_eq!(dark.island_background, "#1113187d");
+
+        let light = WallpaperColors::from_matugen(&json, ColorScheme::Light).unwrap();
+        assert_eq!(light.text, "#3b5f90");
+        assert_eq!(light.island_border, "#545f71");
+    }
+
+    #[test]
+    fn missing_or_broken_colors() {
+        let mut pywal = fixture(PYWAL);
+        pywal["colors"]["color4"] = json!("red; } * { color: red");
+        assert!(WallpaperColors::from_pywal(&pywal).is_none());
+
+        let mut pywal = fixture(PYWAL);
+        pywal["special"].as_object_mut().unwrap().remove("background");
+        assert!(WallpaperColors::from_pywal(&pywal).is_none());
+
+        let mut matugen = fixture(MATUGEN);
+        matugen["colors"]["light"].as_object_mut().unwrap().remove("primary");
+        assert!(WallpaperColors::from_matugen(&matugen, ColorScheme::Light).is_none());
+        assert!(WallpaperColors::from_matugen(&matugen, ColorScheme::Dark).is_some());
+
+        let mut matugen = fixture(MATUGEN_BY_ROLE);
+        matugen["colors"]["secondary"]["dark"] = json!(12);
+        assert!(WallpaperColors::from_matugen(&matugen, ColorScheme::Dark).is_none());
+
+        assert!(WallpaperColors::from_matugen(&json!({ "colors": [] }), ColorScheme::Dark).is_none());
+        assert!(WallpaperColors::from_matugen(&json!({}), ColorScheme::Dark).is_none());
+    }
+
+    #[test]
+    fn hex_colors() {
+        assert_eq!(hex_color(&json!(" #AABBCC ")), Some("#aabbcc".to_string()));
+        assert_eq!(hex_color(&json!("#aabbcc80")), Some("#aabbcc80".to_string()));
+        assert_eq!(hex_color(&json!("#abc")), None);
+        assert_eq!(hex_color(&json!("#aabbcg")), None);
+        assert_eq!(hex_color(&json!("aabbcc")), None);
+        assert_eq!(hex_color(&json!(0xaabbcc)), None);
+
+        assert_eq!(with_alpha("#aabbcc"), "#aabbcc7d");
+        // Colors with an alpha already keep it
+        assert_eq!(with_alpha("#aabbcc80"), "#aabbcc80");
+    }
+}
@@ -0,0 +1,100 @@
+use gtk::glib::{ self, KeyFile, KeyFileFlags };
+use chrono::{ NaiveDate, NaiveTime };
+
+use std::error::Error;
+use std::path::PathBuf;
+
+use crate::config::{ self, expand_home };
+
+// Alarms are saved to $XDG_DATA_HOME/dinfo/alarms.ini every time they change, so they survive restarts.
+// The file can be edited by hand too, it's read on startup:
+//
+// [alarm:Wake up]
+// time=07:30
+// enabled=true
+// sound=~/sounds/bell.oga
+// # The last day it went off, so it doesnt go off twice after a restart
+// last-fired=2025-01-01
+
+/// An alarm that goes off every day
+#[derive(Debug, Clone)]
+pub struct Alarm {
+    pub name: String,
+    pub time: NaiveTime,
+    pub enabled: bool,
+    /// Played when it goes off, instead of the default sound
+    pub sound: Option<PathBuf>,
+    pub last_fired: Option<NaiveDate>
+}
+
+/// Path of the alarms file, $XDG_DATA_HOME/dinfo/alarms.ini
+pub fn alarms_path() -> PathBuf {
+    glib::user_data_dir().join("dinfo").join("alarms.ini")
+}
+
+/// Parses a time like 7:30 or 07:30:00
+pub fn parse_time(text: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(text, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(text, "%H:%M:%S"))
+        .ok()
+}
+
+/// Reads the saved alarms, an empty list if there are none
+pub fn load() -> Vec<Alarm> {
+    let path = alarms_path();
+    if !path.exists() {
+        return Vec::new();
+    }
+
+    let file = KeyFile::new();
+    if let Err(e) = file.load_from_file(&path, KeyFileFlags::NONE) {
+        eprintln!("Failed to load alarms from {}: {e}", path.display());
+        return Vec::new();
+    }
+
+    file.groups()
+        .iter()
+        .filter_map(|group| {
+            let group = group.to_gstr().as_str();
+            let name = group.strip_prefix("alarm:")?;
+
+            let Some(time) = config::string(&file, group, "time").and_then(|t| parse_time(&t)) else {
+                eprintln!("Alarm '{name}' has no valid time in {}, skipping it", path.display());
+                return None;
+            };
+
+            Some(Alarm {
+                name: name.to_string(),
+                time,
+                enabled: config::boolean(&file, group, "enabled").unwrap_or(true),
+                sound: config::string(&file, group, "sound").map(|s| expand_home(&s)),
+                last_fired: config::string(&file, group, "last-fired").and_then(|d| d.parse().ok())
+            })
+        })
+        .collect()
+}
+
+/// Writes the alarms to the alarms file
+pub fn save(alarms: &[Alarm]) -> Result<(), Box<dyn Error>> {
+    let file = KeyFile::new();
+
+    for alarm in alarms {
+        let group = format!("alarm:{}", alarm.name);
+        file.set_string(&group, "time", &alarm.time.format("%H:%M").to_string());
+        file.set_boolean(&group, "enabled", alarm.enabled);
+        if let Some(sound) = &alarm.sound {
+            file.set_string(&group, "sound", &sound.to_string_lossy());
+        }
+        if let Some(last_fired) = alarm.last_fired {
+            file.set_string(&group, "last-fired", &last_fired.to_string());
+        }
+    }
+
+    let path = alarms_path();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    file.save_to_file(&path)?;
+
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use std::time::Duration;
+
+/// Parses a duration like 5m, 1h30m, 90s or 25 (minutes)
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    if let Ok(minutes) = text.parse::<u64>() {
+        return Some(Duration::from_secs(minutes.checked_mul(60)?));
+    }
+
+    let mut total: u64 = 0;
+    let mut number = String::new();
+    for c in text.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'h' | 'm' | 's' => {
+                let n: u64 = number.parse().ok()?;
+                number.clear();
+                let seconds = n.checked_mul(match c {
+                    'h' => 3600,
+                    'm' => 60,
+                    _ => 1
+                })?;
+                total = total.checked_add(seconds)?;
+            },
+            _ => return None
+        }
+    }
+
+    // Trailing numbers without a unit, or an empty text
+    if !number.is_empty() || total == 0 {
+        return None;
+    }
+
+    Some(Duration::from_secs(total))
+}
+
+/// Formats a duration like 4:05 or 1:04:05
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match seconds / 3600 {
+        0 => format!("{}:{:02}", seconds / 60, seconds % 60),
+        hours => format!("{}:{:02}:{:02}", hours, seconds / 60 % 60, seconds % 60)
+    }
+}
+
+/// Formats a duration with tenths of a second, like 4:05.3
+pub fn format_duration_precise(duration: Duration) -> String {
+    format!("{}.{}", format_duration(duration), duration.subsec_millis() / 100)
+}
+
+/// Formats a longer duration in words, like 8h 12m or 5m
+pub fn format_duration_words(duration: Duration) -> String {
+    let minutes = duration.as_secs().div_ceil(60);
+    match minutes / 60 {
+        0 => format!("{}m", minutes),
+        hours => format!("{}h {}m", hours, minutes % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("25"), Some(Duration::from_secs(25 * 60)));
+        assert_eq!(parse_duration("1h30m"), Some(Duration::from_secs(5400)));
+        assert_eq!(parse_duration(" 90s "), Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration("5"), Some(Duration::from_secs(300)));
+        assert_eq!(parse_duration("5x"), None);
+        assert_eq!(parse_duration("1h30"), None);
+        assert_eq!(parse_duration("0m"), None);
+        assert_eq!(parse_duration(""), None);
+    }
+
+    #[test]
+    fn overflow_is_invalid() {
+        assert_eq!(parse_duration(&u64::MAX.to_string()), None);
+        assert_eq!(parse_duration(&format!("{}h", u64::MAX / 1000)), None);
+        assert_eq!(parse_duration(&format!("{}s1s", u64::MAX)), None);
+    }
+}
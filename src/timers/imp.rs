@@ -0,0 +1,48 @@
+use std::cell::{ Cell, RefCell };
+
+use std::sync::OnceLock;
+use glib::subclass::Signal;
+
+use glib::Properties;
+use gtk::glib;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+
+use super::{ Countdown, Stopwatch };
+use super::alarms::Alarm;
+
+#[derive(Default, Properties)]
+#[properties[wrapper_type = super::Timers]]
+pub struct Timers {
+    /// True while a countdown or the stopwatch runs, the clock ticks every second then
+    #[property(get, set)]
+    active: Cell<bool>,
+    pub countdowns: RefCell<Vec<Countdown>>,
+    pub stopwatch: RefCell<Stopwatch>,
+    pub alarms: RefCell<Vec<Alarm>>
+}
+
+impl Timers {
+    pub fn changed(&self) {
+        let obj = self.obj();
+        obj.set_active(!self.countdowns.borrow().is_empty() || self.stopwatch.borrow().is_running());
+        obj.emit_by_name::<()>("changed", &[]);
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for Timers {
+    const NAME: &'static str = "Timers";
+    type Type = super::Timers;
+}
+
+#[glib::derived_properties]
+impl ObjectImpl for Timers {
+    fn signals() -> &'static [Signal] {
+        static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+        SIGNALS.get_or_init(|| {
+            // A countdown, the stopwatch or an alarm was added, removed, started or stopped
+            vec![Signal::builder("changed").build()]
+        })
+    }
+}
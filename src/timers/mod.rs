@@ -0,0 +1,385 @@
+use glib::Object;
+use gtk::{ Application, gio, prelude::*, glib::{ self, subclass::types::ObjectSubclassIsExt } };
+use chrono::{ DateTime, Local, NaiveDate, NaiveTime };
+
+use std::path::PathBuf;
+use std::time::{ Duration, Instant };
+
+mod imp;
+
+pub mod alarms;
+use alarms::Alarm;
+
+mod duration;
+pub use duration::{ format_duration, format_duration_precise, format_duration_words, parse_duration };
+
+use crate::notification;
+
+// Countdown timers, a stopwatch and daily alarms. They dont have their own loop,
+// they are checked on every tick of the clock (which ticks every second while one of them runs).
+//
+// They are controlled through the application's actions, like:
+// gapplication action dinfo.oil653 timer-start "'Tea 5m'"
+
+/// Countdowns cant be longer than this
+const MAX_COUNTDOWN: Duration = Duration::from_secs(366 * 24 * 3600);
+
+/// Alarms missed by at most this much (like during a suspend) still go off
+const MISSED_ALARM_GRACE: chrono::Duration = chrono::Duration::minutes(10);
+
+/// A named timer counting down
+#[derive(Debug, Clone)]
+pub struct Countdown {
+    pub name: String,
+    pub duration: Duration,
+    /// On the wall clock, so it still goes off right after a suspend
+    pub end: DateTime<Local>
+}
+
+impl Countdown {
+    pub fn remaining(&self, now: DateTime<Local>) -> Duration {
+        (self.end - now).to_std().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Stopwatch {
+    /// Set while it's running
+    running_since: Option<Instant>,
+    /// Time measured before the last start
+    elapsed: Duration,
+    /// The total time at every lap
+    pub laps: Vec<Duration>
+}
+
+impl Stopwatch {
+    pub fn is_running(&self) -> bool {
+        self.running_since.is_some()
+    }
+
+    /// True if it's running, or stopped with some time on it
+    pub fn is_used(&self) -> bool {
+        self.is_running() || !self.elapsed.is_zero()
+    }
+
+    pub fn total(&self) -> Duration {
+        self.elapsed + self.running_since.map(|s| s.elapsed()).unwrap_or_default()
+    }
+}
+
+glib::wrapper!{
+    pub struct Timers(ObjectSubclass<imp::Timers>);
+}
+
+impl Timers {
+    pub fn new() -> Self {
+        let obj: Self = Object::builder()
+            .property("active", false)
+            .build();
+
+        obj.imp().alarms.replace(alarms::load());
+
+        obj
+    }
+
+    pub fn countdowns(&self) -> Vec<Countdown> {
+        self.imp().countdowns.borrow().clone()
+    }
+
+    pub fn stopwatch(&self) -> Stopwatch {
+        self.imp().stopwatch.borrow().clone()
+    }
+
+    pub fn alarms(&self) -> Vec<Alarm> {
+        self.imp().alarms.borrow().clone()
+    }
+
+    pub fn connect_changed<F: Fn(&Self) + 'static>(&self, f: F) -> glib::SignalHandlerId {
+        self.connect_closure("changed", false, glib::closure_local!(move |timers: &Self| f(timers)))
+    }
+
+    /// Starts a countdown, replacing the one with the same name.
+    /// Countdowns longer than MAX_COUNTDOWN are rejected
+    pub fn start_countdown(&self, name: &str, duration: Duration) {
+        let end = match duration <= MAX_COUNTDOWN {
+            true => chrono::Duration::from_std(duration).ok().and_then(|length| Local::now().checked_add_signed(length)),
+            false => None
+        };
+        let Some(end) = end else {
+            eprintln!("The '{name}' timer is too long, it can be at most {}", format_duration_words(MAX_COUNTDOWN));
+            return;
+        };
+
+        let mut countdowns = self.imp().countdowns.borrow_mut();
+        countdowns.retain(|c| c.name != name);
+        countdowns.push(Countdown { name: name.to_string(), duration, end });
+        countdowns.sort_by_key(|c| c.end);
+        drop(countdowns);
+
+        self.imp().changed();
+    }
+
+    pub fn cancel_countdown(&self, name: &str) {
+        self.imp().countdowns.borrow_mut().retain(|c| c.name != name);
+        self.imp().changed();
+    }
+
+    /// Starts the stopwatch, or stops it if it's running
+    pub fn toggle_stopwatch(&self) {
+        let mut stopwatch = self.imp().stopwatch.borrow_mut();
+        match stopwatch.running_since.take() {
+            Some(since) => stopwatch.elapsed += since.elapsed(),
+            None => stopwatch.running_since = Some(Instant::now())
+        }
+        drop(stopwatch);
+
+        self.imp().changed();
+    }
+
+    pub fn lap_stopwatch(&self) {
+        let mut stopwatch = self.imp().stopwatch.borrow_mut();
+        if stopwatch.is_running() {
+            let total = stopwatch.total();
+            stopwatch.laps.push(total);
+        }
+        drop(stopwatch);
+
+        self.imp().changed();
+    }
+
+    pub fn reset_stopwatch(&self) {
+        self.imp().stopwatch.replace(Stopwatch::default());
+        self.imp().changed();
+    }
+
+    /// Adds a daily alarm, replacing the one with the same name
+    pub fn add_alarm(&self, name: &str, time: NaiveTime, sound: Option<PathBuf>) {
+        let now = Local::now();
+
+        let mut alarms = self.imp().alarms.borrow_mut();
+        alarms.retain(|a| a.name != name);
+        alarms.push(Alarm {
+            name: name.to_string(),
+            time,
+            enabled: true,
+            sound,
+            // If it's already past the time today, it's for tomorrow
+            last_fired: (time <= now.time()).then_some(now.date_naive())
+        });
+        alarms.sort_by_key(|a| a.time);
+        drop(alarms);
+
+        self.save_alarms();
+        self.imp().changed();
+    }
+
+    /// Removes an alarm by its name
+    pub fn remove_alarm(&self, name: &str) {
+        self.imp().alarms.borrow_mut().retain(|a| a.name != name);
+        self.save_alarms();
+        self.imp().changed();
+    }
+
+    pub fn set_alarm_enabled(&self, name: &str, enabled: bool) {
+        for alarm in self.imp().alarms.borrow_mut().iter_mut().filter(|a| a.name == name) {
+            alarm.enabled = enabled;
+        }
+        self.save_alarms();
+        self.imp().changed();
+    }
+
+    fn save_alarms(&self) {
+        if let Err(e) = alarms::save(&self.imp().alarms.borrow()) {
+            eprintln!("Failed to save alarms: {e}");
+        }
+    }
+
+    /// The next time an alarm goes off
+    pub fn next_alarm(alarm: &Alarm, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        let today = now.date_naive();
+        let day = match alarm.last_fired == Some(today) || alarm.time <= now.time() {
+            true => today.succ_opt()?,
+            false => today
+        };
+
+        day.and_time(alarm.time).and_local_timezone(Local).earliest()
+    }
+
+    /// Sets off the countdowns that ended, and the alarms that are due
+    pub fn tick(&self, now: DateTime<Local>) {
+        let mut changed = false;
+
+        let finished: Vec<Countdown> = {
+            let mut countdowns = self.imp().countdowns.borrow_mut();
+            let (finished, running) = countdowns.drain(..).partition(|c| c.end <= now);
+            *countdowns = running;
+            finished
+        };
+
+        for countdown in finished {
+            notification::send(
+                &format!("timer-{}", countdown.name),
+                &format!("{} is done", countdown.name),
+                &format!("The {} timer is over", format_duration_words(countdown.duration)),
+                gio::NotificationPriority::High
+            );
+            play_default_sound();
+            changed = true;
+        }
+
+        let mut fired_alarm = false;
+        for alarm in self.imp().alarms.borrow_mut().iter_mut().filter(|a| a.enabled) {
+            let Some(day) = due_day(alarm, now) else {
+                continue;
+            };
+
+            notification::send(
+                &format!("alarm-{}", alarm.name),
+                &alarm.name,
+                &format!("It's {}", alarm.time.format("%H:%M")),
+                gio::NotificationPriority::Urgent
+            );
+            match &alarm.sound {
+                Some(sound) => notification::play_sound(sound),
+                None => play_default_sound()
+            }
+
+            alarm.last_fired = Some(day);
+            fired_alarm = true;
+        }
+
+        if fired_alarm {
+            self.save_alarms();
+        }
+
+        if changed || fired_alarm {
+            self.imp().changed();
+        }
+    }
+}
+
+/// The day of the occurrence of the alarm that should go off now, if there is one.
+/// Yesterday's is checked too, so a 23:58 alarm still goes off at 00:01 after a suspend
+fn due_day(alarm: &Alarm, now: DateTime<Local>) -> Option<NaiveDate> {
+    let today = now.date_naive();
+    [Some(today), today.pred_opt()]
+        .into_iter()
+        .flatten()
+        .filter(|day| alarm.last_fired.is_none_or(|last| last < *day))
+        .find(|day| {
+            let Some(at) = day.and_time(alarm.time).and_local_timezone(Local).earliest() else {
+                return false;
+            };
+            let late = now - at;
+            late >= chrono::Duration::zero() && late <= MISSED_ALARM_GRACE
+        })
+}
+
+fn play_default_sound() {
+    if let Some(sound) = &crate::CONFIG.get().unwrap().timers.sound {
+        notification::play_sound(sound);
+    }
+}
+
+/// Splits "Tea 5m" to a name and a value, the name is `default_name` if there is only a value.
+/// `value_first` is for "07:30 Wake up" like texts
+fn split_name(text: &str, default_name: &str, value_first: bool) -> (String, String) {
+    let text = text.trim();
+    let split = match value_first {
+        true => text.split_once(' ').map(|(value, name)| (name, value)),
+        false => text.rsplit_once(' ')
+    };
+
+    match split {
+        Some((name, value)) => (name.trim().to_string(), value.to_string()),
+        None => (default_name.to_string(), text.to_string())
+    }
+}
+
+/// Adds the actions that control the timers to the application:
+/// timer-start "Tea 5m", timer-cancel "Tea", stopwatch-toggle, stopwatch-lap, stopwatch-reset,
+/// alarm-add "07:30 Wake up", alarm-remove "Wake up", alarm-enable "Wake up", alarm-disable "Wake up"
+pub fn add_actions(app: &Application, timers: &Timers) {
+    let with_text = |name: &str, f: fn(&Timers, &str)| {
+        gio::ActionEntry::builder(name)
+            .parameter_type(Some(glib::VariantTy::STRING))
+            .activate(glib::clone!(
+                #[weak]
+                timers,
+                move |_: &Application, _, parameter| {
+                    if let Some(text) = parameter.and_then(|p| p.str()) {
+                        f(&timers, text);
+                    }
+                }
+            ))
+            .build()
+    };
+    let without_text = |name: &str, f: fn(&Timers)| {
+        gio::ActionEntry::builder(name)
+            .activate(glib::clone!(
+                #[weak]
+                timers,
+                move |_: &Application, _, _| f(&timers)
+            ))
+            .build()
+    };
+
+    app.add_action_entries([
+        with_text("timer-start", |timers, text| {
+            let (name, duration) = split_name(text, "Timer", false);
+            match parse_duration(&duration) {
+                Some(duration) => timers.start_countdown(&name, duration),
+                None => eprintln!("Invalid timer '{text}', use a name and a duration like 'Tea 5m'")
+            }
+        }),
+        with_text("timer-cancel", |timers, name| timers.cancel_countdown(name)),
+        without_text("stopwatch-toggle", Timers::toggle_stopwatch),
+        without_text("stopwatch-lap", Timers::lap_stopwatch),
+        without_text("stopwatch-reset", Timers::reset_stopwatch),
+        with_text("alarm-add", |timers, text| {
+            let (name, time) = split_name(text, "Alarm", true);
+            match alarms::parse_time(&time) {
+                Some(time) => timers.add_alarm(&name, time, None),
+                None => eprintln!("Invalid alarm '{text}', use a time and a name like '07:30 Wake up'")
+            }
+        }),
+        with_text("alarm-remove", |timers, name| timers.remove_alarm(name)),
+        with_text("alarm-enable", |timers, name| timers.set_alarm_enabled(name, true)),
+        with_text("alarm-disable", |timers, name| timers.set_alarm_enabled(name, false)),
+    ]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn alarm(time: &str, last_fired: Option<NaiveDate>) -> Alarm {
+        Alarm { name: "Test".to_string(), time: alarms::parse_time(time).unwrap(), enabled: true, sound: None, last_fired }
+    }
+
+    fn at(day: u32, time: &str) -> DateTime<Local> {
+        let time = alarms::parse_time(time).unwrap();
+        Local.from_local_datetime(&NaiveDate::from_ymd_opt(2026, 1, day).unwrap().and_time(time)).earliest().unwrap()
+    }
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 1, day).unwrap()
+    }
+
+    #[test]
+    fn alarm_due_on_time_and_within_grace() {
+        assert_eq!(due_day(&alarm("07:30", Some(day(14))), at(15, "07:30")), Some(day(15)));
+        assert_eq!(due_day(&alarm("07:30", Some(day(14))), at(15, "07:39")), Some(day(15)));
+        assert_eq!(due_day(&alarm("07:30", Some(day(14))), at(15, "07:29")), None);
+        assert_eq!(due_day(&alarm("07:30", Some(day(14))), at(15, "07:41")), None);
+        assert_eq!(due_day(&alarm("07:30", Some(day(15))), at(15, "07:31")), None);
+    }
+
+    #[test]
+    fn alarm_missed_across_midnight() {
+        assert_eq!(due_day(&alarm("23:58", Some(day(13))), at(15, "00:01")), Some(day(14)));
+        assert_eq!(due_day(&alarm("23:58", Some(day(14))), at(15, "00:01")), None);
+        assert_eq!(due_day(&alarm("23:58", None), at(15, "00:09")), None);
+    }
+}
@@ -1,149 +1,819 @@
-use gtk::{ Align, Box as Gbox, Button, GestureClick, Label, Overlay, glib::{self, BindingFlags}, prelude::* };
+use gtk::{ Align, Box as Gbox, Button, DrawingArea, GestureClick, Label, Popover, cairo, glib, prelude::* };
 
-use crate::{ clock::Clock, weather::CurrentWeather };
+use crate::{ clock::{ self, Clock, ticker::WallClockTimer }, weather::{ Cordinates, CurrentWeather } };
+use crate::digital_clock::DigitalClock;
+use crate::analog_clock::build_analog_clock;
+use crate::config::{ ClockFace, WorldClockConfig };
 use crate::weather_state::WeatherState;
+use crate::agenda_state::AgendaState;
+use crate::calendar::{ self, Occurrence };
+use crate::timers::{ self, Timers };
+use crate::astronomy::{ self, Moon, SunTimes };
+use crate::month_view::build_month_view;
 
-use chrono::{ self, Local };
+use chrono::{ self, Local, Offset, Timelike, Utc };
 use std::time::Duration;
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::pin::pin;
+use futures::future;
 
 use tokio::runtime::Runtime;
 use async_channel;
 use std::sync::OnceLock;
 
 use crate::weather;
+use crate::upower::{ BatteryDevice, UPower };
+use crate::battery_graph::build_battery_graph;
+use crate::battery_row::BatteryRow;
+
+/// Replaces the weather classes (see CurrentWeather::css_classes) on a widget with the ones of the new weather.
+/// If there is no weather data, the classes are only removed
+pub fn set_weather_css_classes(widget: &impl IsA<gtk::Widget>, weather: Option<&CurrentWeather>) {
+    for class in CurrentWeather::ALL_CSS_CLASSES {
+        widget.remove_css_class(class);
+    }
+
+    if let Some(weather) = weather {
+        for class in weather.css_classes() {
+            widget.add_css_class(class);
+        }
+    }
+}
 
-fn get_seconds_to_midnight() -> i64 {
-    let now = Local::now();
+fn get_today_date() -> String {
+    Local::now().format(crate::DATE_FORMAT.get().unwrap()).to_string()
+}
 
-    // Get todays midnight (most likely passed this already)
-    let mut midnigth = now
-        .date_naive()
-        .and_hms_opt(0, 0, 0)
-        .unwrap();
+/// Sets the date label to today, with today's holidays after it, and the upcoming holidays in its tooltip
+fn update_date(date: &Label) {
+    let today = Local::now().date_naive();
+    let holidays = crate::HOLIDAYS.get().unwrap();
+
+    let todays = holidays.on(today);
+    match todays.is_empty() {
+        true => date.set_label(&get_today_date()),
+        false => date.set_label(&format!(
+            "{} · {}",
+            get_today_date(),
+            todays.iter().map(|h| h.name()).collect::<Vec<_>>().join(", ")
+        ))
+    }
+
+    // Observances (like Mother's Day) arent days off, they can be styled differently
+    date.remove_css_class("holiday");
+    date.remove_css_class("observance");
+    if todays.iter().any(|h| !h.holiday.observance) {
+        date.add_css_class("holiday");
+    } else if !todays.is_empty() {
+        date.add_css_class("observance");
+    }
+
+    let upcoming = holidays.upcoming(today, crate::CONFIG.get().unwrap().holidays.upcoming);
+    let tooltip = upcoming
+        .iter()
+        .map(|holiday| {
+            let when = match (holiday.date - today).num_days() {
+                1 => "tomorrow".to_string(),
+                d => format!("in {d} days")
+            };
+            format!("{}  {}, {when}", holiday.date.format("%b %-d"), holiday.name())
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    date.set_tooltip_text((!tooltip.is_empty()).then_some(tooltip.as_str()));
+}
 
-    // Add one day so we get the first second of the new day
-    midnigth += Duration::from_hours(24);
+/// Shows if the system clock is synchronized in the clock's tooltip, and a warning under it if it isnt
+fn show_sync_status(clock: &gtk::Widget, warning: &Label, clock_state: &Clock) {
+    if !clock_state.sync_known() {
+        clock.set_tooltip_text(None);
+        clock.remove_css_class("unsynced");
+        warning.set_visible(false);
+        return;
+    }
+
+    let (tooltip, warning_text) = match (clock_state.synced(), clock_state.ntp_enabled()) {
+        (true, _) => ("The clock is synchronized with a time server", ""),
+        (false, true) => ("The clock isn't synchronized with a time server yet, it might be off", "⚠ Clock not synchronized"),
+        (false, false) => ("Time synchronization is turned off, the clock might be off", "⚠ Clock not synchronized, NTP is off")
+    };
 
-    (midnigth - now.naive_local()).num_seconds()
-}
+    clock.set_tooltip_text(Some(tooltip));
+    warning.set_label(warning_text);
+    warning.set_visible(!clock_state.synced());
 
-fn get_today_date() -> String {
-    Local::now().format(crate::DATE_FORMAT.get().unwrap()).to_string()
+    match clock_state.synced() {
+        true => clock.remove_css_class("unsynced"),
+        false => clock.add_css_class("unsynced")
+    }
 }
 
-pub fn build_clock(clock_state: Clock) -> Gbox {
+pub fn build_clock(clock_state: Clock, face: ClockFace, agenda_state: Option<&AgendaState>) -> Gbox {
     //  =========> CLOCK <=========
-        let clock1 = {
+        let config = &crate::CONFIG.get().unwrap().clock;
+        let clock: gtk::Widget = match face {
+            ClockFace::Digital => DigitalClock::new(&clock_state, config.transition).upcast(),
+            ClockFace::Analog => {
+                let clock = build_analog_clock(config.second_hand);
+                // Without a second hand it only redraws every minute
+                clock_state.connect_time_changed(glib::clone!(
+                    #[weak]
+                    clock,
+                    move |_| clock.queue_draw()
+                ));
+                clock.upcast()
+            }
+        };
+
+        let date = {
             Label::builder()
-            .label("12:41:69")
-            .name("clock1")
-            .css_classes(["text", "clock"])
+            .name("date")
+            .css_classes(["text", "emoji"])
             .vexpand(false)
-            .halign(Align::Center)
-            .valign(Align::Center)
             .build()
         };
+        update_date(&date);
 
-        clock_state
-        .bind_property("clock1_label", &clock1, "label")
-        .flags(BindingFlags::DEFAULT)
-        .build();
+        // Wakes up the date's loop when the system wakes up or the time zone changes, midnight moves with the zone
+        let (time_changed_snd, time_changed_rcv) = async_channel::bounded::<()>(1);
+        clock_state.connect_time_changed(move |_| {
+            let _ = time_changed_snd.try_send(());
+        });
 
-        clock_state.connect_closure("clock1-should-fade", false, glib::closure_local!(
+        glib::spawn_future_local(glib::clone!(
             #[weak]
-            clock1,
-            move |_obj: &Clock, new_state: bool| -> () {
-                if new_state {
-                    clock1.add_css_class("fade-out");
-                } else {
-                    clock1.remove_css_class("fade-out");
+            date,
+            async move {
+                // Sleeps on the wall clock, so the date is right after a suspend or a time change too
+                let timer = WallClockTimer::new().ok();
+
+                loop {
+                    let midnight = clock::next_midnight(Local::now());
+                    let sleep = async {
+                        match &timer {
+                            Some(timer) => {
+                                if let Err(e) = timer.sleep_until(midnight).await {
+                                    eprintln!("Wall clock timer failed: {e}");
+                                    glib::timeout_future(clock::time_until(midnight, Local::now())).await;
+                                }
+                            },
+                            None => glib::timeout_future(clock::time_until(midnight, Local::now())).await
+                        }
+                    };
+
+                    future::select(pin!(sleep), pin!(time_changed_rcv.recv())).await;
+                    update_date(&date);
                 }
             }
         ));
 
-        let clock2 = { 
-            Label::builder()
-            .label("12:41:69")
-            .name("clock2")
-            .css_classes(["text", "clock"])
-            .vexpand(false)
-            .halign(Align::Center)
-            .valign(Align::Center)
+        // Clicking the date opens the month view, always on the current month
+        let ( month_view, go_to_today ) = build_month_view(&crate::CONFIG.get().unwrap().calendar);
+        let calendar = {
+            Popover::builder()
+            .child(&month_view)
+            .css_classes(["calendar_popover"])
             .build()
         };
+        calendar.set_parent(&date);
+        calendar.connect_show(move |_| go_to_today());
 
-        clock_state
-        .bind_property("clock2_label", &clock2, "label")
-        .flags(BindingFlags::DEFAULT)
-        .build();
-
-        clock_state.connect_closure("clock2-should-fade", false, glib::closure_local!(
+        let click = GestureClick::new();
+        click.connect_pressed(glib::clone!(
             #[weak]
-            clock2,
-            move |_obj: &Clock, new_state: bool| -> () {
-                if new_state {
-                    clock2.add_css_class("fade-out");
-                } else {
-                    clock2.remove_css_class("fade-out");
-                }
-            }
+            calendar,
+            move |_, _, _, _| calendar.popup()
         ));
+        date.add_controller(click);
 
-        let clock = {
-            Overlay::builder()
-            .halign(Align::Center)
-            .valign(Align::Center)
-            .child(&clock1)
+        let time_date_box = {
+            Gbox::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(0)
             .build()
         };
-
-        clock.add_overlay(&clock2);
-        
-        let date = {
+        let sync_warning = {
             Label::builder()
-            .label(get_today_date())
-            .name("date")
-            .css_classes(["text", "emoji"])
-            .vexpand(false)
+            .name("sync_warning")
+            .css_classes(["text", "sync_warning"])
+            .visible(false)
             .build()
         };
 
-        glib::spawn_future_local(glib::clone!(
+        let update_sync_status = glib::clone!(
             #[weak]
-            date,
-            async move {
-                // println!("{} seconds till midnight", get_seconds_to_midnight());
-                glib::timeout_future_seconds(get_seconds_to_midnight() as u32).await;
+            clock,
+            #[weak]
+            sync_warning,
+            #[weak]
+            clock_state,
+            move || show_sync_status(&clock, &sync_warning, &clock_state)
+        );
+        update_sync_status();
+        clock_state.connect_sync_known_notify(glib::clone!(
+            #[strong]
+            update_sync_status,
+            move |_| update_sync_status()
+        ));
+        clock_state.connect_synced_notify(glib::clone!(
+            #[strong]
+            update_sync_status,
+            move |_| update_sync_status()
+        ));
+        clock_state.connect_ntp_enabled_notify(move |_| update_sync_status());
 
-                date.set_label(get_today_date().as_str());
+        time_date_box.append(&clock);
+        time_date_box.append(&sync_warning);
+        time_date_box.append(&date);
+        if let Some(countdowns) = build_countdowns(&clock_state) {
+            time_date_box.append(&countdowns);
+        }
+        if let Some(agenda_state) = agenda_state {
+            time_date_box.append(&build_agenda(agenda_state));
+        }
 
-                loop {
-                    glib::timeout_future_seconds(get_seconds_to_midnight() as u32).await;
-                    date.set_label(get_today_date().as_str());
+        time_date_box
+}
+
+/// Formats the time left like "12 days 04:30 left"
+fn format_time_left(days: u64, rest: Duration) -> String {
+    let minutes = rest.as_secs() / 60;
+    let time = format!("{:02}:{:02}", minutes / 60, minutes % 60);
+
+    match days {
+        0 => format!("{time} left"),
+        1 => format!("1 day {time} left"),
+        d => format!("{d} days {time} left")
+    }
+}
+
+/// The configured countdowns under the date, None if there are none configured.
+/// They are hidden once they pass
+fn build_countdowns(clock_state: &Clock) -> Option<Gbox> {
+    let countdowns = &crate::CONFIG.get().unwrap().countdowns;
+    if countdowns.is_empty() {
+        return None;
+    }
+
+    let container = {
+        Gbox::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .name("countdowns")
+        .css_classes(["countdowns"])
+        .build()
+    };
+
+    let mut entries = Vec::new();
+    for countdown in countdowns {
+        let entry = {
+            Gbox::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .css_classes(["countdown"])
+            .halign(Align::Center)
+            .spacing(8)
+            .tooltip_text(countdown.target.format("%Y-%m-%d %H:%M").to_string())
+            .build()
+        };
+        let name = {
+            Label::builder()
+            .label(&countdown.name)
+            .css_classes(["text", "countdown_name"])
+            .build()
+        };
+        let left = {
+            Label::builder()
+            .css_classes(["text", "countdown_left"])
+            .build()
+        };
+        entry.append(&name);
+        entry.append(&left);
+        container.append(&entry);
+
+        entries.push((entry, left, countdown.target));
+    }
+
+    let update = glib::clone!(
+        #[weak]
+        container,
+        move || {
+            let now = Local::now();
+            let mut any_left = false;
+
+            for (entry, left, target) in &entries {
+                match clock::days_until(*target, now) {
+                    Some((days, rest)) => {
+                        left.set_label(&format_time_left(days, rest));
+                        entry.set_visible(true);
+                        any_left = true;
+                    },
+                    None => entry.set_visible(false)
                 }
             }
-        ));
 
-        let time_date_box = {
+            container.set_visible(any_left);
+        }
+    );
+
+    update();
+    clock_state.connect_tick(move |_| update());
+
+    Some(container)
+}
+
+/// Formats a UTC offset like UTC+2, UTC-3 or UTC+5:30
+fn format_utc_offset(seconds: i32) -> String {
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let minutes = seconds.abs() / 60;
+
+    match minutes % 60 {
+        0 => format!("UTC{}{}", sign, minutes / 60),
+        m => format!("UTC{}{}:{:02}", sign, minutes / 60, m)
+    }
+}
+
+/// Refreshes the labels of a world clock entry
+fn update_world_clock(entry: &Gbox, time: &Label, offset: &Label, world_clock: &WorldClockConfig, pattern: &str) {
+    let now = Utc::now().with_timezone(&world_clock.zone);
+
+    // Without a location, daytime is guessed to be between 6 and 18 o'clock
+    let is_day = match &world_clock.location {
+        Some(location) => astronomy::is_day(location, now),
+        None => (6..18).contains(&now.hour())
+    };
+
+    let day_difference = match (now.date_naive() - Local::now().date_naive()).num_days() {
+        0 => String::new(),
+        1 => ", tomorrow".to_string(),
+        -1 => ", yesterday".to_string(),
+        d => format!(", {:+} days", d)
+    };
+
+    time.set_label(&format!("{} {}", if is_day { "☀️" } else { "🌙" }, now.format(pattern)));
+    offset.set_label(&format!("{}{}", format_utc_offset(now.offset().fix().local_minus_utc()), day_difference));
+
+    entry.remove_css_class(if is_day { "night" } else { "day" });
+    entry.add_css_class(if is_day { "day" } else { "night" });
+}
+
+/// A row of clocks for the configured time zones, None if there are none configured.
+/// They update together with the main clock
+pub fn build_world_clocks(clock_state: &Clock) -> Option<Gbox> {
+    let config = crate::CONFIG.get().unwrap();
+    if config.world_clocks.is_empty() {
+        return None;
+    }
+
+    let pattern = config.clock.format.minutes_pattern();
+
+    let row = {
+        Gbox::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .name("world_clocks")
+        .css_classes(["world_clocks"])
+        .homogeneous(true)
+        .spacing(10)
+        .build()
+    };
+
+    for world_clock in &config.world_clocks {
+        let entry = {
             Gbox::builder()
             .orientation(gtk::Orientation::Vertical)
-            .spacing(0)
+            .css_classes(["island", "world_clock"])
+            .tooltip_text(world_clock.zone.name())
             .build()
         };
-        time_date_box.append(&clock);
-        time_date_box.append(&date);
 
-        time_date_box
+        let name = {
+            Label::builder()
+            .label(world_clock.name.as_str())
+            .css_classes(["text", "world_clock_name"])
+            .build()
+        };
+
+        let time = {
+            Label::builder()
+            .css_classes(["text", "emoji", "world_clock_time"])
+            .build()
+        };
+
+        let offset = {
+            Label::builder()
+            .css_classes(["text", "world_clock_offset"])
+            .build()
+        };
+
+        entry.append(&name);
+        entry.append(&time);
+        entry.append(&offset);
+        row.append(&entry);
+
+        update_world_clock(&entry, &time, &offset, world_clock, &pattern);
+
+        clock_state.connect_label_notify(glib::clone!(
+            #[weak]
+            entry,
+            #[weak]
+            time,
+            #[weak]
+            offset,
+            #[strong]
+            pattern,
+            move |_| {
+                update_world_clock(&entry, &time, &offset, world_clock, &pattern);
+            }
+        ));
+    }
+
+    Some(row)
+}
+
+/// A row of the timers, an icon and a name on the left, and the time on the right
+fn build_timer_row(icon: &str, name: &str, tooltip: &str) -> (Gbox, Label) {
+    let row = {
+        Gbox::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .css_classes(["timer"])
+        .tooltip_text(tooltip)
+        .spacing(10)
+        .build()
+    };
+
+    let name = {
+        Label::builder()
+        .label(format!("{} {}", icon, name))
+        .css_classes(["text", "emoji", "timer_name"])
+        .halign(Align::Start)
+        .hexpand(true)
+        .ellipsize(gtk::pango::EllipsizeMode::End)
+        .build()
+    };
+
+    let value = {
+        Label::builder()
+        .css_classes(["text", "timer_value"])
+        .halign(Align::End)
+        .build()
+    };
+
+    row.append(&name);
+    row.append(&value);
+    (row, value)
 }
 
+/// Lists the running countdowns, the stopwatch and the enabled alarms with the time left.
+/// It's hidden if none of them are active. The rows are only rebuilt when the timers change,
+/// on every tick of the clock just the times are updated, so tooltips and clicks arent lost
+pub fn build_timers(timers: &Timers, clock_state: &Clock) -> Gbox {
+    let container = {
+        Gbox::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .name("timers")
+        .css_classes(["timers"])
+        .spacing(2)
+        .build()
+    };
+
+    // Sets the time on a row
+    type Updater = Box<dyn Fn(chrono::DateTime<Local>)>;
+    let updaters: Rc<RefCell<Vec<Updater>>> = Rc::default();
+
+    let update_times = Rc::new(glib::clone!(
+        #[strong]
+        updaters,
+        move || {
+            let now = Local::now();
+            for update in updaters.borrow().iter() {
+                update(now);
+            }
+        }
+    ));
 
-fn runtime() -> &'static Runtime {
+    let build_rows = Rc::new(glib::clone!(
+        #[weak]
+        container,
+        #[weak]
+        timers,
+        #[strong]
+        updaters,
+        #[strong]
+        update_times,
+        move || {
+            while let Some(child) = container.first_child() {
+                container.remove(&child);
+            }
+            let mut new_updaters: Vec<Updater> = Vec::new();
+
+            for countdown in timers.countdowns() {
+                let (row, value) = build_timer_row(
+                    "⏲️",
+                    &countdown.name,
+                    &format!("{} timer, ends at {}\nClick to cancel", timers::format_duration_words(countdown.duration), countdown.end.format("%H:%M:%S"))
+                );
+
+                let click = GestureClick::new();
+                click.connect_pressed(glib::clone!(
+                    #[weak]
+                    timers,
+                    #[strong(rename_to = name)]
+                    countdown.name,
+                    move |_, _, _, _| timers.cancel_countdown(&name)
+                ));
+                row.add_controller(click);
+                container.append(&row);
+
+                new_updaters.push(Box::new(move |now| value.set_label(&timers::format_duration(countdown.remaining(now)))));
+            }
+
+            let stopwatch = timers.stopwatch();
+            if stopwatch.is_used() {
+                let laps: Vec<String> = stopwatch.laps
+                    .iter()
+                    .enumerate()
+                    .map(|(i, lap)| format!("Lap {}: {}", i + 1, timers::format_duration_precise(*lap)))
+                    .collect();
+
+                let (row, value) = build_timer_row(
+                    "⏱️",
+                    &match stopwatch.laps.len() {
+                        0 => "Stopwatch".to_string(),
+                        n => format!("Stopwatch, lap {}", n + 1)
+                    },
+                    format!("{}\nClick to {}", laps.join("\n"), if stopwatch.is_running() { "stop" } else { "continue" }).trim_start()
+                );
+                if !stopwatch.is_running() {
+                    row.add_css_class("paused");
+                }
+
+                let click = GestureClick::new();
+                click.connect_pressed(glib::clone!(
+                    #[weak]
+                    timers,
+                    move |_, _, _, _| timers.toggle_stopwatch()
+                ));
+                row.add_controller(click);
+                container.append(&row);
+
+                new_updaters.push(Box::new(move |_| value.set_label(&timers::format_duration(stopwatch.total()))));
+            }
+
+            for alarm in timers.alarms().into_iter().filter(|a| a.enabled) {
+                let (row, value) = build_timer_row(
+                    "⏰",
+                    &alarm.name,
+                    &format!("Goes off every day at {}", alarm.time.format("%H:%M"))
+                );
+                container.append(&row);
+
+                new_updaters.push(Box::new(move |now| {
+                    if let Some(next) = Timers::next_alarm(&alarm, now) {
+                        value.set_label(&format!("{}, in {}", alarm.time.format("%H:%M"), timers::format_duration_words((next - now).to_std().unwrap_or_default())));
+                    }
+                }));
+            }
+
+            updaters.replace(new_updaters);
+            update_times();
+
+            container.set_visible(container.first_child().is_some());
+        }
+    ));
+
+    build_rows();
+
+    timers.connect_changed(move |_| build_rows());
+    clock_state.connect_tick(move |_| update_times());
+
+    container
+}
+
+/// Events starting within this are highlighted
+const AGENDA_SOON: chrono::Duration = chrono::Duration::minutes(10);
+
+/// A row of the agenda, the time and the summary of the event
+fn build_agenda_event(occurrence: &Occurrence, pattern: &str, now: chrono::DateTime<Local>) -> Gbox {
+    let row = {
+        Gbox::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .css_classes(["agenda_event"])
+        .spacing(10)
+        .build()
+    };
+
+    let time_range = match occurrence.all_day {
+        true => "All day".to_string(),
+        false => format!("{} - {}", occurrence.start.format(pattern), occurrence.end.format(pattern))
+    };
+
+    let time = {
+        Label::builder()
+        .label(if occurrence.all_day { "All day".to_string() } else { occurrence.start.format(pattern).to_string() })
+        .css_classes(["text", "agenda_time"])
+        .halign(Align::Start)
+        .build()
+    };
+
+    let summary = {
+        Label::builder()
+        .label(occurrence.summary.as_str())
+        .css_classes(["text", "agenda_summary"])
+        .halign(Align::Start)
+        .hexpand(true)
+        .ellipsize(gtk::pango::EllipsizeMode::End)
+        .build()
+    };
+
+    row.set_tooltip_text(Some(&match &occurrence.location {
+        Some(location) => format!("{}\n{}\n{}", occurrence.summary, time_range, location),
+        None => format!("{}\n{}", occurrence.summary, time_range)
+    }));
+
+    if !occurrence.all_day && occurrence.start > now && occurrence.start - now <= AGENDA_SOON {
+        row.add_css_class("soon");
+    } else if occurrence.end <= now && !occurrence.all_day {
+        row.add_css_class("past");
+    } else if occurrence.start <= now {
+        row.add_css_class("ongoing");
+    }
+
+    row.append(&time);
+    row.append(&summary);
+    row
+}
+
+/// Lists today's and tomorrow's events from the calendars, the ones starting in 10 minutes are highlighted.
+/// It's updated when the calendars are reloaded, and every minute
+pub fn build_agenda(agenda_state: &AgendaState) -> Gbox {
+    let agenda = {
+        Gbox::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .name("agenda")
+        .css_classes(["agenda"])
+        .spacing(2)
+        .build()
+    };
+
+    let pattern = crate::CONFIG.get().unwrap().clock.format.minutes_pattern();
+
+    let update_ui = Rc::new(glib::clone!(
+        #[weak]
+        agenda,
+        #[strong]
+        agenda_state,
+        move || {
+            while let Some(child) = agenda.first_child() {
+                agenda.remove(&child);
+            }
+
+            let now = Local::now();
+            let today = now.date_naive();
+            let events = agenda_state.get_events();
+
+            for (title, day) in [("Today", today), ("Tomorrow", today + chrono::Days::new(1))] {
+                let Some(from) = day.and_time(chrono::NaiveTime::MIN).and_local_timezone(Local).earliest() else {
+                    continue;
+                };
+                let Some(to) = (day + chrono::Days::new(1)).and_time(chrono::NaiveTime::MIN).and_local_timezone(Local).earliest() else {
+                    continue;
+                };
+
+                let header = {
+                    Label::builder()
+                    .label(title)
+                    .css_classes(["text", "agenda_day"])
+                    .halign(Align::Start)
+                    .build()
+                };
+                agenda.append(&header);
+
+                let occurrences = calendar::occurrences_between(&events, from, to);
+                if occurrences.is_empty() {
+                    let empty = {
+                        Label::builder()
+                        .label("No events")
+                        .css_classes(["text", "agenda_empty"])
+                        .halign(Align::Start)
+                        .build()
+                    };
+                    agenda.append(&empty);
+                }
+
+                for occurrence in &occurrences {
+                    agenda.append(&build_agenda_event(occurrence, &pattern, now));
+                }
+            }
+        }
+    ));
+
+    update_ui();
+
+    agenda_state.connect_is_loading_notify(glib::clone!(
+        #[strong]
+        update_ui,
+        move |state| {
+            if !state.is_loading() {
+                update_ui();
+            }
+        }
+    ));
+
+    // Keep the highlight and the days up to date, on the start of every minute
+    glib::spawn_future_local(glib::clone!(
+        #[strong] update_ui,
+        async move {
+            loop {
+                glib::timeout_future_seconds(60 - Local::now().second()).await;
+                update_ui();
+            }
+        }
+    ));
+
+    agenda
+}
+
+pub fn runtime() -> &'static Runtime {
     static RUNTIME: OnceLock<Runtime> = OnceLock::new();
     RUNTIME.get_or_init(|| {
         Runtime::new().expect("Setting up tokio runtime needs to succeed.")
     })
 }
 
+/// Formats the seconds UPower estimates like 1h 20m, or 45m under an hour
+pub fn format_battery_time(seconds: i64) -> String {
+    let minutes = seconds / 60;
+    match minutes / 60 {
+        0 => format!("{}m", minutes),
+        hours => format!("{}h {:02}m", hours, minutes % 60)
+    }
+}
+
+/// The battery island, listing the UPower devices that have a battery. It's hidden while there are none.
+/// The list follows the changes of UPower's store of BatteryDevices, the main battery's history is under it
+pub fn build_battery(upower: &UPower) -> Gbox {
+    let devices = upower.devices();
+
+    let battery = {
+        Gbox::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .name("battery")
+        .css_classes(["island", "battery"])
+        .valign(Align::Center)
+        .hexpand(true)
+        .margin_start(5)
+        .margin_end(5)
+        .margin_top(10)
+        .build()
+    };
+
+    // AC adapters are devices too, but they have no charge to show
+    let filter = gtk::CustomFilter::new(|item| {
+        item.downcast_ref::<BatteryDevice>().is_some_and(|d| d.has_battery())
+    });
+    let model = gtk::FilterListModel::new(Some(devices), Some(filter));
+
+    // The rows are made once, and then reused for the devices they are bound to
+    let factory = gtk::SignalListItemFactory::new();
+    factory.connect_setup(|_, item| {
+        if let Some(item) = item.downcast_ref::<gtk::ListItem>() {
+            item.set_child(Some(&BatteryRow::new()));
+        }
+    });
+    factory.connect_bind(|_, item| {
+        let Some(item) = item.downcast_ref::<gtk::ListItem>() else {
+            return;
+        };
+        if let (Some(row), Some(device)) = (item.child().and_downcast::<BatteryRow>(), item.item().and_downcast::<BatteryDevice>()) {
+            row.bind(&device);
+        }
+    });
+    factory.connect_unbind(|_, item| {
+        if let Some(row) = item.downcast_ref::<gtk::ListItem>().and_then(|i| i.child()).and_downcast::<BatteryRow>() {
+            row.unbind();
+        }
+    });
+
+    let list = {
+        gtk::ListView::builder()
+        .model(&gtk::NoSelection::new(Some(model.clone())))
+        .factory(&factory)
+        .css_classes(["battery_list"])
+        .build()
+    };
+    battery.append(&list);
+    battery.append(&build_battery_graph(upower));
+
+    battery.set_visible(model.n_items() > 0);
+    model.connect_items_changed(glib::clone!(
+        #[weak]
+        battery,
+        move |model, _, _, _| battery.set_visible(model.n_items() > 0)
+    ));
+
+    battery
+}
+
 pub fn build_current_weather(
     current_weather_state: &WeatherState, 
     weather_result_sender: async_channel::Sender<Option<CurrentWeather>>, 
@@ -334,6 +1004,8 @@ pub fn build_current_weather(
                 wind.set_label(&format!("💨 {} {}", wh.wind.direction_stringify(), wh.wind.speed_stringify()));
 
                 status_string.set_label(&wh.weather_code.to_string());
+
+                set_weather_css_classes(&current_weather, Some(&wh));
             } else {
                 println!("Empty weather data recieved :(");
 
@@ -366,6 +1038,8 @@ pub fn build_current_weather(
                 wind.set_label("");
 
                 status_string.set_label("");
+
+                set_weather_css_classes(&current_weather, None);
             }
         }
     ));
@@ -419,4 +1093,230 @@ pub fn build_current_weather(
     current_weather.add_controller(click);
 
     (current_weather, update_ui)
-}
\ No newline at end of file
+}
+/// Formats an optional sun event time, "--:--" if it doesnt happen that day
+fn sun_event_to_string(time: Option<chrono::DateTime<Local>>) -> String {
+    match time {
+        Some(t) => t.format("%H:%M").to_string(),
+        None => "--:--".to_string()
+    }
+}
+
+/// Draws the sun's elevation over the day, the horizon, and the sun's current position
+fn draw_sun_arc(area: &DrawingArea, cr: &cairo::Context, width: i32, height: i32, loc: &Cordinates) {
+    let width = width as f64;
+    let height = height as f64;
+    let padding = 8.0;
+
+    let color = area.color();
+    let horizon = height / 2.0;
+    let elevation_to_y = |elevation: f64| horizon - elevation / 90.0 * (horizon - padding);
+
+    let midnight = Local::now()
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_local_timezone(Local)
+        .earliest();
+    let Some(midnight) = midnight else {
+        return;
+    };
+
+    // Sample the sun's elevation every few pixels across the day
+    let samples = (width / 4.0).max(2.0) as i32;
+    let day_seconds = 24.0 * 60.0 * 60.0;
+    cr.move_to(0.0, elevation_to_y(astronomy::sun_elevation(loc, midnight)));
+    for i in 1..=samples {
+        let fraction = i as f64 / samples as f64;
+        let time = midnight + chrono::Duration::seconds((fraction * day_seconds) as i64);
+        cr.line_to(fraction * width, elevation_to_y(astronomy::sun_elevation(loc, time)));
+    }
+    let curve = cr.copy_path();
+
+    // Dimmed arc below the horizon
+    cr.set_source_rgba(color.red() as f64, color.green() as f64, color.blue() as f64, 0.3);
+    cr.set_line_width(2.0);
+    let _ = cr.stroke();
+
+    // Full color arc above the horizon
+    if let Ok(curve) = curve {
+        let _ = cr.save();
+        cr.rectangle(0.0, 0.0, width, horizon);
+        cr.clip();
+        cr.append_path(&curve);
+        cr.set_source_color(&color);
+        cr.set_line_width(3.0);
+        let _ = cr.stroke();
+        let _ = cr.restore();
+    }
+
+    // Horizon
+    cr.set_source_rgba(color.red() as f64, color.green() as f64, color.blue() as f64, 0.6);
+    cr.set_line_width(1.0);
+    cr.move_to(0.0, horizon);
+    cr.line_to(width, horizon);
+    let _ = cr.stroke();
+
+    // The sun itself
+    let now = Local::now();
+    let fraction = (now - midnight).num_seconds() as f64 / day_seconds;
+    let elevation = astronomy::sun_elevation(loc, now);
+    cr.set_source_color(&color);
+    cr.arc(fraction * width, elevation_to_y(elevation), 6.0, 0.0, 2.0 * std::f64::consts::PI);
+    let _ = cr.fill();
+}
+
+/// Builds a widget showing the sun's path over the day, sunrise, sunset and the moon phase.
+/// The location is taken from the current weather, the returned closure updates the widget after a weather parse
+pub fn build_sun_arc(current_weather_state: &WeatherState) -> (Gbox, Box<dyn Fn()>) {
+    let current_weather_state = current_weather_state.clone();
+
+    let arc = {
+        DrawingArea::builder()
+        .content_height(110)
+        .content_width(400)
+        .css_classes(["text", "sun_arc"])
+        .hexpand(true)
+        .build()
+    };
+
+    arc.set_draw_func(glib::clone!(
+        #[strong] current_weather_state,
+        move |area, cr, width, height| {
+            if let Some(wh) = current_weather_state.get_current() {
+                draw_sun_arc(area, cr, width, height, &wh.location);
+            }
+        }
+    ));
+
+    let sunrise = {
+        Label::builder()
+        .css_classes(["text", "current_weather_text"])
+        .halign(Align::Start)
+        .hexpand(true)
+        .build()
+    };
+
+    let day_length = {
+        Label::builder()
+        .css_classes(["text", "current_weather_text"])
+        .tooltip_text("Time between sunrise and sunset")
+        .halign(Align::Center)
+        .build()
+    };
+
+    let moon = {
+        Label::builder()
+        .css_classes(["text", "emoji", "current_weather_text"])
+        .halign(Align::Center)
+        .margin_start(10)
+        .margin_end(10)
+        .build()
+    };
+
+    let sunset = {
+        Label::builder()
+        .css_classes(["text", "current_weather_text"])
+        .halign(Align::End)
+        .hexpand(true)
+        .build()
+    };
+
+    let times = {
+        Gbox::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .margin_start(10)
+        .margin_end(10)
+        .margin_bottom(5)
+        .build()
+    };
+    times.append(&sunrise);
+    times.append(&day_length);
+    times.append(&moon);
+    times.append(&sunset);
+
+    let sun_arc = {
+        Gbox::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .css_classes(["island"])
+        .halign(Align::Center)
+        .hexpand(true)
+        .margin_start(5)
+        .margin_end(5)
+        .margin_top(10)
+        .visible(false)
+        .build()
+    };
+    sun_arc.append(&arc);
+    sun_arc.append(&times);
+
+    let update_ui = Rc::new(glib::clone!(
+        #[strong] arc,
+        #[strong] sunrise,
+        #[strong] sunset,
+        #[strong] day_length,
+        #[strong] moon,
+        #[strong] sun_arc,
+        #[strong] current_weather_state,
+        move || {
+            let Some(wh) = current_weather_state.get_current() else {
+                sun_arc.set_visible(false);
+                return;
+            };
+            sun_arc.set_visible(true);
+
+            let sun = SunTimes::today(&wh.location);
+
+            sunrise.set_label(&format!("🌅 {}", sun_event_to_string(sun.sunrise.morning)));
+            sunset.set_label(&format!("🌇 {}", sun_event_to_string(sun.sunrise.evening)));
+
+            let minutes = sun.day_length.as_secs() / 60;
+            day_length.set_label(&format!("{}h {:02}m", minutes / 60, minutes % 60));
+
+            arc.set_tooltip_text(Some(&format!(
+                "Nautical dawn: {}\nCivil dawn: {}\nGolden hour: {} - {}\nSolar noon: {}\nGolden hour: {} - {}\nCivil dusk: {}\nNautical dusk: {}",
+                sun_event_to_string(sun.nautical.morning),
+                sun_event_to_string(sun.civil.morning),
+                sun_event_to_string(sun.golden_hour_bounds.morning),
+                sun_event_to_string(sun.golden_hour.morning),
+                sun.noon.format("%H:%M"),
+                sun_event_to_string(sun.golden_hour.evening),
+                sun_event_to_string(sun.golden_hour_bounds.evening),
+                sun_event_to_string(sun.civil.evening),
+                sun_event_to_string(sun.nautical.evening)
+            )));
+
+            let current_moon = Moon::now();
+            moon.set_label(&current_moon.phase.to_emoji());
+            moon.set_tooltip_text(Some(&format!(
+                "{}, {:.0}% illuminated\n{:.1} days since the new moon",
+                current_moon.phase,
+                current_moon.illumination * 100.0,
+                current_moon.age
+            )));
+
+            arc.queue_draw();
+        }
+    ));
+
+    // Move the sun along, and roll the times over to the next day. The arc (and update_ui, which holds it)
+    // is only held while drawing, so the loop stops when it's destroyed
+    let arc_ref = arc.downgrade();
+    let update_ref = Rc::downgrade(&update_ui);
+    glib::spawn_future_local(async move {
+        let mut day = Local::now().date_naive();
+        loop {
+            glib::timeout_future_seconds(60).await;
+            let (Some(arc), Some(update_ui)) = (arc_ref.upgrade(), update_ref.upgrade()) else {
+                break;
+            };
+            if day != Local::now().date_naive() {
+                day = Local::now().date_naive();
+                update_ui();
+            }
+            arc.queue_draw();
+        }
+    });
+
+    (sun_arc, Box::new(move || update_ui()))
+}
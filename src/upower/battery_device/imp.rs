@@ -0,0 +1,57 @@
+use std::cell::{ Cell, RefCell };
+
+use glib::Properties;
+use gtk::glib;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+
+#[derive(Default, Properties)]
+#[properties[wrapper_type = super::BatteryDevice]]
+pub struct BatteryDevice {
+    /// The UPower object path, like /org/freedesktop/UPower/devices/battery_BAT0
+    #[property(get, set)]
+    path: RefCell<String>,
+    #[property(get, set)]
+    name: RefCell<String>,
+    /// UPower's device type, see helpers::type_name
+    #[property(get, set)]
+    kind: Cell<u32>,
+    #[property(get, set)]
+    icon_name: RefCell<String>,
+    /// 0-100
+    #[property(get, set)]
+    percentage: Cell<f64>,
+    /// UPower's battery state, see upower_dbus::BatteryState
+    #[property(get, set)]
+    state: Cell<u32>,
+    /// Seconds until it's empty, 0 if it's unknown or it's charging
+    #[property(get, set)]
+    time_to_empty: Cell<i64>,
+    /// Seconds until it's full, 0 if it's unknown or it's discharging
+    #[property(get, set)]
+    time_to_full: Cell<i64>,
+    /// Watts going in or out of the battery, 0 if it's unknown
+    #[property(get, set)]
+    energy_rate: Cell<f64>,
+    /// Connected through bluez
+    #[property(get, set)]
+    bluetooth: Cell<bool>,
+    /// The task following the device's changes, stopped when the object goes away
+    pub watcher: RefCell<Option<glib::JoinHandle<()>>>
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for BatteryDevice {
+    const NAME: &'static str = "BatteryDevice";
+    type Type = super::BatteryDevice;
+}
+
+#[glib::derived_properties]
+impl ObjectImpl for BatteryDevice {
+    fn dispose(&self) {
+        // A removed device's D-Bus streams would stay open until its next change otherwise
+        if let Some(watcher) = self.watcher.take() {
+            watcher.abort();
+        }
+    }
+}
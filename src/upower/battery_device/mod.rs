@@ -0,0 +1,58 @@
+use glib::Object;
+use gtk::glib::{ self, prelude::*, subclass::types::ObjectSubclassIsExt };
+
+mod imp;
+
+use super::{ Device, helpers };
+
+// A UPower device as a GObject, so labels can bind to its properties.
+// It's kept up to date by its Device's poll_self
+
+glib::wrapper!{
+    pub struct BatteryDevice(ObjectSubclass<imp::BatteryDevice>);
+}
+
+impl BatteryDevice {
+    /// Creates the object with the device's current values, and starts following its changes
+    pub fn new(device: Device) -> Self {
+        let obj: Self = Object::builder()
+            .property("path", &device.path)
+            .property("name", &device.name)
+            .property("kind", device.kind)
+            .property("icon_name", &device.icon_name)
+            .property("percentage", device.percentage)
+            .property("state", device.state as u32)
+            .property("time_to_empty", device.time_to_empty)
+            .property("time_to_full", device.time_to_full)
+            .property("energy_rate", device.energy_rate)
+            .property("bluetooth", device.bluetooth)
+            .build();
+
+        let weak = obj.downgrade();
+        let watcher = glib::spawn_future_local(async move {
+            device.poll_self(weak).await;
+        });
+        obj.imp().watcher.replace(Some(watcher));
+
+        obj
+    }
+
+    /// False for AC adapters, which have no battery to show
+    pub fn has_battery(&self) -> bool {
+        self.kind() != helpers::LINE_POWER
+    }
+
+    /// The name of its kind in the config, like headset
+    pub fn kind_key(&self) -> String {
+        helpers::type_name(self.kind()).to_lowercase().replace(' ', "-")
+    }
+
+    /// The icon to show. Batteries get a level icon from their charge and state,
+    /// other devices (like mice) keep their own icon, that says more about what they are
+    pub fn status_icon(&self) -> String {
+        match self.kind() {
+            helpers::BATTERY | helpers::UPS => helpers::level_icon(self.percentage(), self.state()),
+            _ => self.icon_name()
+        }
+    }
+}
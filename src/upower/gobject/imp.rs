@@ -1,30 +1,30 @@
 use std::cell::RefCell;
-use std::sync::OnceLock;
-
-use async_lock::Mutex;
 
 use glib::Properties;
-use gtk::glib;
-use gtk::glib::subclass::Signal;
+use gtk::{ gio, glib };
 use gtk::prelude::*;
 use gtk::subclass::prelude::*;
 
-use super::super::Device;
+use super::super::BatteryDevice;
 use zbus::Connection;
 
-#[derive(Default, Properties)]
+#[derive(Properties)]
 #[properties[wrapper_type = super::UPower]]
 pub struct UPower {
     #[property(get, set)]
     init_success: RefCell<bool>,
-    pub devices: Mutex<Vec<Device>>,
+    /// BatteryDevices, in the order UPower listed (or added) them
+    pub devices: gio::ListStore,
     pub connection: RefCell<Option<Connection>>
 }
 
-impl UPower {
-    pub async fn get_devices(&self) -> Vec<Device> {
-        let guard = self.devices.lock().await;
-        (*guard.clone()).to_vec()
+impl Default for UPower {
+    fn default() -> Self {
+        UPower {
+            init_success: RefCell::new(false),
+            devices: gio::ListStore::new::<BatteryDevice>(),
+            connection: RefCell::new(None)
+        }
     }
 }
 
@@ -35,13 +35,4 @@ impl ObjectSubclass for UPower {
 }
 
 #[glib::derived_properties]
-impl ObjectImpl for UPower {
-    fn signals() -> &'static [Signal] {
-        static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
-        SIGNALS.get_or_init(|| {
-            vec![
-                Signal::builder("devices-changed").build(),
-            ]
-        })
-    }
-}
\ No newline at end of file
+impl ObjectImpl for UPower {}
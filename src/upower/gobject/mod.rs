@@ -1,30 +1,88 @@
 use glib::Object;
-use gtk::glib::{self, object::ObjectExt, subclass::types::ObjectSubclassIsExt};
+use gtk::{ gio, prelude::*, glib::{ self, subclass::types::ObjectSubclassIsExt } };
 
 use upower_dbus::UPowerProxy;
-use zbus::{Connection, zvariant::OwnedObjectPath};
+use zbus::Connection;
 
 use futures::{ select, stream::StreamExt, pin_mut };
 
 mod imp;
 
-use super::Device;
+use super::{ BatteryDevice, Device, helpers };
 
-// The way this works: 
-// Devices are automatically added and removed from a Vec (the Vec is fully remade)
-// The individual devices are lazy, they dont update their state, but they contain the stream you can await
-// on to get the percentage change
+// The way this works:
+// The devices are BatteryDevice GObjects in a gio::ListStore, keyed by their object path.
+// When UPower adds or removes a device only that item is added or removed, so the UI can
+// use the store as a model (like in a ListView), and follow the changes of the items themselves.
 
 glib::wrapper!{
     pub struct UPower(ObjectSubclass<imp::UPower>);
 }
 
 impl UPower {
-    pub async fn new() -> Self {
+    /// Creates the object with an empty store, the devices are added once UPower is reached
+    pub fn new() -> Self {
         let obj: UPower = Object::builder()
         .property("init_success", false)
         .build();
 
+        glib::spawn_future_local(glib::clone!(
+            #[weak]
+            obj,
+            async move {
+                obj.init().await;
+            }
+        ));
+
+        obj
+    }
+
+    /// The devices, a gio::ListModel of BatteryDevices
+    pub fn devices(&self) -> gio::ListStore {
+        self.imp().devices.clone()
+    }
+
+    /// The system bus connection, None until UPower is reached
+    pub fn connection(&self) -> Option<Connection> {
+        self.imp().connection.borrow().clone()
+    }
+
+    /// The laptop's own battery, the first device UPower calls a battery.
+    /// None on desktops, where only UPS-es and peripherals have batteries
+    pub fn main_battery(&self) -> Option<BatteryDevice> {
+        let devices = &self.imp().devices;
+        (0..devices.n_items())
+            .filter_map(|i| devices.item(i).and_downcast::<BatteryDevice>())
+            .find(|d| d.kind() == helpers::BATTERY)
+    }
+
+    /// The position of a device in the store
+    fn find(&self, path: &str) -> Option<u32> {
+        let devices = &self.imp().devices;
+        (0..devices.n_items()).find(|&i| {
+            devices.item(i).and_downcast::<BatteryDevice>().is_some_and(|d| d.path() == path)
+        })
+    }
+
+    /// Adds a device to the store, unless it's already there
+    async fn add_device(&self, connection: &Connection, path: &str) {
+        if self.find(path).is_some() {
+            return;
+        }
+
+        match Device::new(connection, path.to_string()).await {
+            Ok(device) => self.imp().devices.append(&BatteryDevice::new(device)),
+            Err(e) => eprintln!("Failed to create device struct from {path}: {e}")
+        }
+    }
+
+    fn remove_device(&self, path: &str) {
+        if let Some(position) = self.find(path) {
+            self.imp().devices.remove(position);
+        }
+    }
+
+    async fn init(&self) {
         let connection= match Connection::system().await {
             Ok(c) => {
                 println!("Successfully connected to system DBus");
@@ -32,7 +90,7 @@ impl UPower {
             },
             Err(e) => {
                 eprintln!("Failed to connect to system DBus bus: {e}");
-                return obj
+                return;
             }
         };
 
@@ -40,7 +98,23 @@ impl UPower {
             Ok(p) => p,
             Err(e) => {
                 eprintln!("Failed to create UPower proxy while creating new UPower GObject: {e}");
-                return obj;
+                return;
+            }
+        };
+
+        // Subscribe before enumerating, so a device added in between isnt missed
+        let added = match upower.receive_device_added().await {
+            Ok(d) => d.fuse(),
+            Err(e) => {
+                eprintln!("Failed to get device_added signal stream: {e}");
+                return;
+            }
+        };
+        let removed = match upower.receive_device_removed().await {
+            Ok(d) => d.fuse(),
+            Err(e) => {
+                eprintln!("Failed to get device_removed signal stream: {e}");
+                return;
             }
         };
 
@@ -48,133 +122,53 @@ impl UPower {
             Ok(d) => d,
             Err(e) => {
                 eprintln!("Failed to run enumerate_devices() from upower proxy: {e}");
-                return obj;
+                return;
             }
         };
 
-        let devices: Vec<Device> = Self::parse_devices(&connection, &device_paths).await;
-
-        let imp = obj.imp();
-        {
-            let mut guard = imp.devices.lock().await;
-            *guard = devices;
+        for path in &device_paths {
+            self.add_device(&connection, path.as_str()).await;
         }
-        imp.connection.replace(Some(connection));
 
-        obj.set_property("init_success", true);
-
-        let uproxy = upower.clone();
-        glib::spawn_future_local(glib::clone!(
-            #[strong]
-            obj,
-            async move {
-                let update_devices = async move || {
-                    let connection = obj.imp().connection.borrow();
-                    let connection = connection.as_ref().expect("Connection is None, expected to be Some");
-
-                    let device_paths = match upower.enumerate_devices().await {
-                        Ok(d) => d,
-                        Err(e) => {
-                            eprintln!("Failed to run enumerate_devices() from upower proxy: {e}");
-                            Vec::new()
-                        }
-                    };
-                    
-                    let devices: Vec<Device> = Self::parse_devices(&connection, &device_paths).await;
-
-                    let imp = obj.imp();
-                    {
-                        let mut guard = imp.devices.lock().await;
-                        *guard = devices;
-                    }
+        self.imp().connection.replace(Some(connection.clone()));
+        self.set_init_success(true);
 
-                    obj.emit_by_name::<()>("devices-changed", &[]);
-                };
+        pin_mut!(added, removed);
 
-                let added = match uproxy.receive_device_added().await{
-                    Ok(d) => d.fuse(),
-                    Err(e) => {
-                        eprintln!("Failed to get device_added signal stream: {e}");
-                        return;
-                    }
-                };
-                let removed = match uproxy.receive_device_removed().await {
-                    Ok(d) => d.fuse(),
-                    Err(e) => {
-                        eprintln!("Failed to get device_removed signal stream: {e}");
-                        return;
-                    }
-                };
-
-                pin_mut!(added, removed);
-
-                let mut added_finished = false;
-                let mut removed_finished = false;
-                loop {
-                    if added_finished && removed_finished {
-                        eprintln!("Both added and removed stream on UPower finished. Device add/removes will not update the state automatically");
-                        break;
-                    }
-
-                    if added_finished {
-                        eprintln!("Added stream on UPower finished. Device adds will not update the state automatically")
-                    }
+        let mut added_finished = false;
+        let mut removed_finished = false;
+        loop {
+            if added_finished && removed_finished {
+                eprintln!("Both added and removed stream on UPower finished. Device add/removes will not update the state automatically");
+                break;
+            }
 
-                    if removed_finished {
-                        eprintln!("Removed stream on UPower finished. Device removes will not update the state automatically")
+            select! {
+                item = added.next() => {
+                    match item {
+                        Some(signal) => match signal.args() {
+                            Ok(args) => self.add_device(&connection, args.device().as_str()).await,
+                            Err(e) => eprintln!("Failed to parse DeviceAdded signal from UPower: {e}")
+                        },
+                        None => {
+                            eprintln!("Added stream on UPower finished. Device adds will not update the state automatically");
+                            added_finished = true;
+                        }
                     }
-
-                    select! {
-                        item = added.next() => {
-                            match item {
-                                Some(_) => {
-                                    // do stuff
-                                    println!("Meow");
-                                    update_devices().await;
-                                }
-                                None => {
-                                    added_finished = true;
-                                }
-                            }
+                },
+                item = removed.next() => {
+                    match item {
+                        Some(signal) => match signal.args() {
+                            Ok(args) => self.remove_device(args.device().as_str()),
+                            Err(e) => eprintln!("Failed to parse DeviceRemoved signal from UPower: {e}")
                         },
-                        item = removed.next() => {
-                            match item {
-                                Some(_) => {
-                                    // do stuff
-                                    println!("Meow");
-                                    update_devices().await;
-                                }
-                                None => {
-                                    removed_finished = true;
-                                }
-                            }
+                        None => {
+                            eprintln!("Removed stream on UPower finished. Device removes will not update the state automatically");
+                            removed_finished = true;
                         }
                     }
                 }
-            })
-        );
-
-        obj
-    }
-
-    async fn parse_devices(connection: &Connection, device_paths: &Vec<OwnedObjectPath>) -> Vec<Device> {
-        let mut devices: Vec<Device> = Vec::new();
-
-        for device in device_paths {
-            let device = match Device::new(&connection, device.to_string()).await {
-                Ok(d) => d,
-                Err(e) => {
-                    eprintln!("Failed to create device struct from {device}: {e}");
-                    continue;
-                }
-            };
-            devices.push(device);
+            }
         }
-
-        devices
     }
-
-    pub async fn get_devices(&self) -> Vec<Device> {
-        self.imp().get_devices().await
-    }
-}
\ No newline at end of file
+}
@@ -1,6 +1,6 @@
 use std::error::Error;
 
-use upower_dbus::{ self, DeviceProxy }; 
+use upower_dbus::{ self, BatteryState, DeviceProxy }; 
 use zbus::Connection;
 
 use std::collections::HashMap;
@@ -10,6 +10,58 @@ use std::collections::HashMap;
 // For more information about this, check out my prototype repo: 
 // https://github.com/oil653/upower-example-rs.git
 
+/// UPower's type of AC adapters, they have no battery
+pub const LINE_POWER: u32 = 1;
+pub const BATTERY: u32 = 2;
+pub const UPS: u32 = 3;
+
+/// A readable name for UPower's device type, used when the device has no model name
+pub fn type_name(kind: u32) -> &'static str {
+    match kind {
+        LINE_POWER => "Power supply",
+        BATTERY => "Battery",
+        UPS => "UPS",
+        4 => "Monitor",
+        5 => "Mouse",
+        6 => "Keyboard",
+        7 => "PDA",
+        8 => "Phone",
+        9 => "Media player",
+        10 => "Tablet",
+        11 => "Computer",
+        12 => "Controller",
+        13 => "Pen",
+        14 => "Touchpad",
+        15 => "Modem",
+        16 => "Network device",
+        17 => "Headset",
+        18 => "Speakers",
+        19 => "Headphones",
+        20 => "Video device",
+        21 => "Audio device",
+        22 => "Remote control",
+        23 => "Printer",
+        24 => "Scanner",
+        25 => "Camera",
+        26 => "Wearable",
+        27 => "Toy",
+        28 => "Bluetooth device",
+        _ => "Device"
+    }
+}
+
+/// A battery-level icon from the icon theme for a charge and a BatteryState (as a number),
+/// like battery-level-70-charging-symbolic. The levels go in steps of 10
+pub fn level_icon(percentage: f64, state: u32) -> String {
+    let level = ((percentage / 10.0).round() as u32).min(10) * 10;
+
+    match state {
+        s if s == BatteryState::FullyCharged as u32 => "battery-level-100-charged-symbolic".to_string(),
+        s if s == BatteryState::Charging as u32 => format!("battery-level-{level}-charging-symbolic"),
+        _ => format!("battery-level-{level}-symbolic")
+    }
+}
+
 /// Check if a device is a bluez device or not
 pub async fn is_bluez(dev: &DeviceProxy<'_>) -> bool {
     match dev.native_path().await {
@@ -0,0 +1,254 @@
+use std::error::Error;
+use std::time::Duration;
+
+use upower_dbus::DeviceProxy;
+use zbus::Connection;
+
+// UPower keeps a history of every battery's charge and energy rate, and statistics of how long
+// every percentage took to charge or discharge, with how accurate they are. upower_dbus doesnt have the methods, so they are called by name.
+// https://upower.freedesktop.org/docs/Device.html
+//
+// UPower doesnt know when the screen is on. The screen-on time is estimated as the time spent discharging,
+// without the suspends: UPower adds a point every time the charge changes, so a long gap between two points is a suspend.
+
+/// How far back the history goes
+pub const HISTORY_SPAN: Duration = Duration::from_secs(24 * 60 * 60);
+/// How many points UPower returns at most, per kind
+const HISTORY_RESOLUTION: u32 = 200;
+/// A longer gap between two points while discharging is taken as a suspend
+const MAX_POINT_GAP: Duration = Duration::from_secs(20 * 60);
+
+#[derive(Debug, Clone, Copy)]
+pub struct HistoryPoint {
+    /// Unix time
+    pub time: i64,
+    /// Percent for the charge, watts for the rate
+    pub value: f64,
+    /// UPower's battery state, see upower_dbus::BatteryState
+    pub state: u32
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BatteryHistory {
+    /// Oldest first
+    pub charge: Vec<HistoryPoint>,
+    /// Oldest first
+    pub rate: Vec<HistoryPoint>,
+    /// Wh when it's full
+    pub energy_full: f64,
+    /// UPower's discharge statistics for every percentage (0-100): how long it took relative to the others,
+    /// and how accurate that is in percent. Empty if it has none
+    pub discharge_profile: Vec<(f64, f64)>,
+    /// None if UPower doesnt know (or is older than 0.99.14)
+    pub charge_cycles: Option<i32>
+}
+
+impl BatteryHistory {
+    /// The average watts used while discharging, None if it wasnt discharging
+    pub fn average_discharge_rate(&self, discharging: u32) -> Option<f64> {
+        let rates: Vec<f64> = self.rate
+            .iter()
+            .filter(|p| p.state == discharging && p.value > 0.0)
+            .map(|p| p.value)
+            .collect();
+
+        match rates.is_empty() {
+            true => None,
+            false => Some(rates.iter().sum::<f64>() / rates.len() as f64)
+        }
+    }
+
+    /// The time spent discharging in the history, an estimate of the screen-on time on battery
+    pub fn screen_on_time(&self, discharging: u32) -> Duration {
+        let seconds: i64 = self.charge
+            .windows(2)
+            .filter(|pair| pair[0].state == discharging)
+            .map(|pair| (pair[1].time - pair[0].time).clamp(0, MAX_POINT_GAP.as_secs() as i64))
+            .sum();
+
+        Duration::from_secs(seconds as u64)
+    }
+
+    /// How long discharging each percentage takes relative to the average (1.0), from UPower's statistics.
+    /// The percentages it has no statistics for are 1.0
+    fn percentage_factors(&self) -> Vec<f64> {
+        let known = |(value, accuracy): &(f64, f64)| (*value > 0.0 && *accuracy > 0.0).then_some(*value);
+
+        let values: Vec<f64> = self.discharge_profile.iter().filter_map(known).collect();
+        let average = values.iter().sum::<f64>() / values.len().max(1) as f64;
+
+        (0..=100)
+            .map(|i| self.discharge_profile.get(i).and_then(known).map(|v| v / average).unwrap_or(1.0))
+            .collect()
+    }
+
+    /// How long the battery lasts from `percentage` to empty, using `rate` watts on average.
+    /// UPower's statistics correct it for the percentages that go faster or slower
+    pub fn runtime_from(&self, percentage: f64, rate: f64) -> Option<Duration> {
+        if self.energy_full <= 0.0 || rate <= 0.0 {
+            return None;
+        }
+
+        // Discharging 1% at the average rate
+        let seconds_per_percent = self.energy_full / 100.0 / rate * 3600.0;
+
+        let percentage = percentage.clamp(0.0, 100.0);
+        let factors = self.percentage_factors();
+        // Going from `p` to `p - 1` takes the time of percentage `p`
+        let whole = percentage.floor() as usize;
+        let percents: f64 = factors[1..=whole].iter().sum::<f64>()
+            + (percentage - whole as f64) * factors[(whole + 1).min(100)];
+
+        Some(Duration::from_secs_f64(percents * seconds_per_percent))
+    }
+
+    /// How accurate UPower's discharge statistics are on average in percent, over the percentages it has them for
+    pub fn statistics_accuracy(&self) -> Option<f64> {
+        let known: Vec<f64> = self.discharge_profile.iter().map(|(_, accuracy)| *accuracy).filter(|a| *a > 0.0).collect();
+
+        match known.is_empty() {
+            true => None,
+            false => Some(known.iter().sum::<f64>() / known.len() as f64)
+        }
+    }
+
+    /// The charge at the end of the history
+    pub fn last_percentage(&self) -> Option<f64> {
+        self.charge.last().map(|p| p.value)
+    }
+}
+
+async fn get_history(device: &DeviceProxy<'_>, kind: &str) -> zbus::Result<Vec<HistoryPoint>> {
+    let points: Vec<(u32, f64, u32)> = device.inner()
+        .call("GetHistory", &(kind, HISTORY_SPAN.as_secs() as u32, HISTORY_RESOLUTION))
+        .await?;
+
+    // UPower returns them newest first
+    let mut points: Vec<HistoryPoint> = points
+        .into_iter()
+        .map(|(time, value, state)| HistoryPoint { time: time as i64, value, state })
+        .collect();
+    points.sort_by_key(|p| p.time);
+
+    Ok(points)
+}
+
+/// UPower's discharging statistics, (value, accuracy) for every percentage
+async fn get_discharge_profile(device: &DeviceProxy<'_>) -> zbus::Result<Vec<(f64, f64)>> {
+    device.inner().call("GetStatistics", &("discharging",)).await
+}
+
+/// Reads the last day of a battery's history from UPower
+pub async fn read(connection: &Connection, path: &str) -> Result<BatteryHistory, Box<dyn Error>> {
+    let device = DeviceProxy::new(connection, path.to_string()).await?;
+
+    if !device.has_history().await? {
+        return Err(format!("UPower keeps no history of {path}").into());
+    }
+
+    let discharge_profile = match device.has_statistics().await? {
+        true => get_discharge_profile(&device).await.unwrap_or_else(|e| {
+            eprintln!("Failed to get the statistics of {path} from UPower: {e}");
+            Vec::new()
+        }),
+        false => Vec::new()
+    };
+
+    Ok(BatteryHistory {
+        charge: get_history(&device, "charge").await?,
+        rate: get_history(&device, "rate").await?,
+        energy_full: device.energy_full().await?,
+        discharge_profile,
+        charge_cycles: device.inner().get_property::<i32>("ChargeCycles").await.ok().filter(|c| *c >= 0)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use upower_dbus::BatteryState;
+
+    const DISCHARGING: u32 = BatteryState::Discharging as u32;
+    const CHARGING: u32 = BatteryState::Charging as u32;
+
+    fn point(minute: i64, value: f64, state: u32) -> HistoryPoint {
+        HistoryPoint { time: 1_800_000_000 + minute * 60, value, state }
+    }
+
+    fn history(charge: Vec<HistoryPoint>, rate: Vec<HistoryPoint>) -> BatteryHistory {
+        BatteryHistory { charge, rate, energy_full: 50.0, ..Default::default() }
+    }
+
+    #[test]
+    fn average_discharge_rate() {
+        let history = history(Vec::new(), vec![
+            point(0, 20.0, CHARGING),
+            point(10, 6.0, DISCHARGING),
+            point(20, 10.0, DISCHARGING),
+            // UPower reports 0 when it doesnt know the rate
+            point(30, 0.0, DISCHARGING),
+            point(40, 8.0, DISCHARGING),
+        ]);
+        assert_eq!(history.average_discharge_rate(DISCHARGING), Some(8.0));
+        assert_eq!(history.average_discharge_rate(CHARGING), Some(20.0));
+        assert_eq!(history.average_discharge_rate(4), None);
+        assert_eq!(BatteryHistory::default().average_discharge_rate(DISCHARGING), None);
+    }
+
+    #[test]
+    fn screen_on_time_skips_suspends() {
+        let history = history(vec![
+            point(0, 100.0, DISCHARGING),
+            point(10, 98.0, DISCHARGING),
+            point(25, 95.0, DISCHARGING),
+            // Suspended for 8 hours, only 20 minutes of it count
+            point(505, 93.0, DISCHARGING),
+            point(515, 91.0, CHARGING),
+            point(575, 100.0, DISCHARGING),
+            point(590, 98.0, DISCHARGING),
+        ], Vec::new());
+
+        assert_eq!(history.screen_on_time(DISCHARGING), Duration::from_secs((10 + 15 + 20 + 10 + 15) * 60));
+        assert_eq!(BatteryHistory::default().screen_on_time(DISCHARGING), Duration::ZERO);
+    }
+
+    #[test]
+    fn runtime_at_the_average_rate() {
+        // 50 Wh at 10 W is 5 hours
+        let history = history(Vec::new(), Vec::new());
+        assert_eq!(history.runtime_from(100.0, 10.0), Some(Duration::from_secs(5 * 3600)));
+        assert_eq!(history.runtime_from(50.0, 10.0), Some(Duration::from_secs(5 * 1800)));
+        assert_eq!(history.runtime_from(0.0, 10.0), Some(Duration::ZERO));
+        assert_eq!(history.runtime_from(100.0, 0.0), None);
+        assert_eq!(BatteryHistory::default().runtime_from(100.0, 10.0), None);
+    }
+
+    #[test]
+    fn runtime_with_the_discharge_profile() {
+        // The lower half goes twice as fast as the upper half, 1 and 2 relative to each other
+        let profile: Vec<(f64, f64)> = (0..=100).map(|p| (if p <= 50 { 1.0 } else { 2.0 }, 80.0)).collect();
+        let history = BatteryHistory { discharge_profile: profile, ..history(Vec::new(), Vec::new()) };
+
+        // The factors are 2/3 and 4/3 of the average, so a full charge still lasts about as long
+        let full = history.runtime_from(100.0, 10.0).unwrap().as_secs_f64();
+        assert!((full - 5.0 * 3600.0).abs() < 3.0 * 60.0, "{full}");
+        // But the lower half only lasts a third of it
+        let half = history.runtime_from(50.0, 10.0).unwrap().as_secs_f64();
+        assert!((half - full / 3.0).abs() < 60.0, "{half}");
+
+        assert_eq!(history.statistics_accuracy(), Some(80.0));
+    }
+
+    #[test]
+    fn unknown_percentages_in_the_profile() {
+        // Only a few percentages are known, the rest count as average
+        let mut profile = vec![(0.0, 0.0); 101];
+        profile[10] = (3.0, 50.0);
+        profile[20] = (3.0, 100.0);
+        let history = BatteryHistory { discharge_profile: profile, ..history(Vec::new(), Vec::new()) };
+
+        assert_eq!(history.runtime_from(100.0, 10.0), Some(Duration::from_secs(5 * 3600)));
+        assert_eq!(history.statistics_accuracy(), Some(75.0));
+        assert_eq!(BatteryHistory::default().statistics_accuracy(), None);
+    }
+}
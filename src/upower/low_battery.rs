@@ -0,0 +1,122 @@
+use gtk::{ gio, glib, prelude::* };
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use upower_dbus::BatteryState;
+
+use super::{ BatteryDevice, UPower };
+use crate::config::BatteryThresholds;
+use crate::notification;
+
+// Every device with a battery is watched on its own. Going under the low threshold sends one notification,
+// going under the critical one replaces it with an urgent one. They are sent again only after the device
+// charged back above the threshold (or was plugged in), so a charge jumping around the threshold doesnt spam.
+
+/// How far above the low threshold the charge has to get before it can notify again
+const REARM_MARGIN: f64 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Level {
+    Normal,
+    Low,
+    Critical
+}
+
+fn is_charging(state: u32) -> bool {
+    [BatteryState::Charging, BatteryState::FullyCharged, BatteryState::PendingCharge]
+        .iter()
+        .any(|s| *s as u32 == state)
+}
+
+/// The level a device is at now, `current` is the one it was notified about
+fn level(device: &BatteryDevice, thresholds: BatteryThresholds, current: Level) -> Level {
+    let percentage = device.percentage();
+
+    // UPower reports 0 for devices it doesnt know the charge of
+    if is_charging(device.state()) || percentage <= 0.0 {
+        return Level::Normal;
+    }
+
+    if percentage <= thresholds.critical {
+        Level::Critical
+    } else if percentage <= thresholds.low {
+        current.max(Level::Low)
+    } else if percentage <= thresholds.low + REARM_MARGIN {
+        current
+    } else {
+        Level::Normal
+    }
+}
+
+fn notify(device: &BatteryDevice, level: Level) {
+    let id = format!("battery-{}", device.path());
+    let left = format!("{:.0}% left", device.percentage());
+
+    match level {
+        Level::Normal => notification::withdraw(&id),
+        Level::Low => notification::send(
+            &id,
+            &format!("{} is low", device.name()),
+            &left,
+            gio::NotificationPriority::High
+        ),
+        Level::Critical => notification::send(
+            &id,
+            &format!("{} is almost empty", device.name()),
+            &format!("{left}, charge it now"),
+            gio::NotificationPriority::Urgent
+        )
+    }
+}
+
+/// Follows a device's charge, notifying when it goes under a threshold
+fn watch_device(device: &BatteryDevice) {
+    let config = &crate::CONFIG.get().unwrap().battery;
+    let thresholds = config.thresholds_for(&device.kind_key(), device.bluetooth());
+    let notified = Cell::new(Level::Normal);
+
+    let check = move |device: &BatteryDevice| {
+        let level = level(device, thresholds, notified.get());
+        if level == notified.get() {
+            return;
+        }
+
+        // Only going down notifies, recovering just takes the old notification back
+        if level > notified.get() {
+            println!("{} is at {:.0}%, sending a low battery notification", device.name(), device.percentage());
+        }
+        notify(device, level);
+        notified.set(level);
+    };
+
+    // A device that's already low when it shows up is notified about too
+    check(device);
+    let check = Rc::new(check);
+    for property in ["percentage", "state"] {
+        device.connect_notify_local(Some(property), glib::clone!(
+            #[strong]
+            check,
+            move |device, _| check(device)
+        ));
+    }
+}
+
+/// Sends low battery notifications for the devices in UPower's store, and the ones added later
+pub fn watch(upower: &UPower) {
+    if !crate::CONFIG.get().unwrap().battery.notify {
+        return;
+    }
+
+    let devices = upower.devices();
+    let watch_items = |devices: &gio::ListStore, position: u32, added: u32| {
+        for i in position..position + added {
+            if let Some(device) = devices.item(i).and_downcast::<BatteryDevice>().filter(|d| d.has_battery()) {
+                watch_device(&device);
+            }
+        }
+    };
+
+    watch_items(&devices, 0, devices.n_items());
+    devices.connect_items_changed(move |devices, position, _, added| watch_items(devices, position, added));
+}
@@ -1,11 +1,13 @@
 use std::error::Error;
 
-use upower_dbus::{ self, DeviceProxy };
+use upower_dbus::{ self, BatteryState, DeviceProxy };
 use zbus::{ PropertyStream, Connection };
 
 use gtk::glib;
+use async_lock::Mutex;
+use futures::stream::{ self, StreamExt };
 
-use std::sync::{ Arc, Mutex };
+use std::sync::Arc;
 
 mod helpers;
 
@@ -13,27 +15,124 @@ mod helpers;
 mod gobject;
 pub use gobject::UPower;
 
+mod battery_device;
+pub use battery_device::BatteryDevice;
+
+pub mod low_battery;
+pub mod history;
+
 #[derive(Debug, Clone)]
 pub struct Device {
+    /// The UPower object path
+    pub path: String,
     pub name: String,
+    /// UPower's device type, see helpers::type_name. It's read as a number,
+    /// because upower_dbus' BatteryType doesnt have the newer ones (like headsets)
+    pub kind: u32,
     pub percentage: f64,
     pub icon_name: String,
-    pub percentage_changed: Arc<Mutex<PropertyStream<'static, f64>>>
+    pub state: BatteryState,
+    /// Seconds, 0 if it's unknown
+    pub time_to_empty: i64,
+    /// Seconds, 0 if it's unknown
+    pub time_to_full: i64,
+    /// Watts going in or out of the battery, 0 if it's unknown
+    pub energy_rate: f64,
+    /// Connected through bluez
+    pub bluetooth: bool,
+    pub percentage_changed: Arc<Mutex<PropertyStream<'static, f64>>>,
+    proxy: DeviceProxy<'static>
+}
+
+/// A change of one of the properties poll_self follows
+enum Change {
+    Percentage(f64),
+    State(BatteryState),
+    TimeToEmpty(i64),
+    TimeToFull(i64),
+    EnergyRate(f64),
+    /// The icon has to be looked up again, it might come from bluez
+    IconName
 }
 
 impl Device {
     pub async fn new(connection: &Connection, device_path: String) -> Result<Self, Box<dyn Error>> {
-        let device = DeviceProxy::new(&connection, device_path).await?;
+        let device = DeviceProxy::new(&connection, device_path.clone()).await?;
         
-        let name = device.model().await?;
+        let kind = device.inner().get_property::<u32>("Type").await?;
+        // Laptop batteries often have no model name
+        let name = match device.model().await? {
+            model if model.trim().is_empty() => helpers::type_name(kind).to_string(),
+            model => model
+        };
         let percentage = device.percentage().await?;
         let icon_name = helpers::get_icon(&device, &connection).await;
+        let state = device.state().await?;
+        // upower_dbus doesnt have these
+        let time_to_empty = device.inner().get_property::<i64>("TimeToEmpty").await?;
+        let time_to_full = device.inner().get_property::<i64>("TimeToFull").await?;
+        let energy_rate = device.inner().get_property::<f64>("EnergyRate").await?;
+        let bluetooth = helpers::is_bluez(&device).await;
         let percentage_changed: Arc<Mutex<PropertyStream<'_, f64>>> = Arc::new(Mutex::new(device.receive_percentage_changed().await));
 
-        Ok(Device { name, percentage, icon_name, percentage_changed })
+        Ok(Device {
+            path: device_path,
+            name,
+            kind,
+            percentage,
+            icon_name,
+            state,
+            time_to_empty,
+            time_to_full,
+            energy_rate,
+            bluetooth,
+            percentage_changed,
+            proxy: device
+        })
     }
 
-    pub async fn poll_self<F>(&self, F: F) {
-        // Yet to be implemented
+    /// Pushes the changes of the percentage, the state, the time to empty/full, the energy rate and the icon into `object`.
+    /// Runs until the property streams end, or the object is dropped
+    pub async fn poll_self(&self, object: glib::WeakRef<BatteryDevice>) {
+        let mut percentage_changed = self.percentage_changed.lock().await;
+
+        let percentage = (&mut *percentage_changed)
+            .then(|c| async move { c.get().await.map(Change::Percentage) })
+            .boxed_local();
+        let state = self.proxy.receive_state_changed().await
+            .then(|c| async move { c.get().await.map(Change::State) })
+            .boxed_local();
+        let time_to_empty = self.proxy.inner().receive_property_changed::<i64>("TimeToEmpty").await
+            .then(|c| async move { c.get().await.map(Change::TimeToEmpty) })
+            .boxed_local();
+        let time_to_full = self.proxy.inner().receive_property_changed::<i64>("TimeToFull").await
+            .then(|c| async move { c.get().await.map(Change::TimeToFull) })
+            .boxed_local();
+        let energy_rate = self.proxy.inner().receive_property_changed::<f64>("EnergyRate").await
+            .then(|c| async move { c.get().await.map(Change::EnergyRate) })
+            .boxed_local();
+        let icon_name = self.proxy.receive_icon_name_changed().await
+            .map(|_| Ok(Change::IconName))
+            .boxed_local();
+
+        let mut changes = stream::select_all([percentage, state, time_to_empty, time_to_full, energy_rate, icon_name]);
+
+        while let Some(change) = changes.next().await {
+            let Some(object) = object.upgrade() else {
+                break;
+            };
+
+            match change {
+                Ok(Change::Percentage(percentage)) => object.set_percentage(percentage),
+                Ok(Change::State(state)) => object.set_state(state as u32),
+                Ok(Change::TimeToEmpty(seconds)) => object.set_time_to_empty(seconds),
+                Ok(Change::TimeToFull(seconds)) => object.set_time_to_full(seconds),
+                Ok(Change::EnergyRate(watts)) => object.set_energy_rate(watts),
+                Ok(Change::IconName) => {
+                    object.set_icon_name(helpers::get_icon(&self.proxy, self.proxy.inner().connection()).await);
+                },
+                Err(e) => eprintln!("Failed to read a changed property of {}: {e}", self.name)
+            }
+        }
     }
 }
\ No newline at end of file
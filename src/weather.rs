@@ -1,4 +1,5 @@
 use crate::units as Unit;
+use crate::astronomy;
 pub use crate::units::Units;
 
 use public_ip_address;
@@ -79,6 +80,25 @@ impl Temperature {
     pub fn new(value: f64, unit: Unit::Temperature) -> Self {
         Self {value, unit}
     }
+
+    /// The temperature converted to celsius
+    pub fn celsius(&self) -> f64 {
+        match self.unit {
+            Unit::Temperature::Celsius => self.value,
+            Unit::Temperature::Fahrenheit => (self.value - 32.0) * 5.0 / 9.0
+        }
+    }
+
+    /// CSS class of the temperature band this temperature falls into
+    pub fn css_class(&self) -> &'static str {
+        match self.celsius() {
+            t if t < 0.0 => "freezing",
+            t if t < 10.0 => "cold",
+            t if t < 20.0 => "mild",
+            t if t < 28.0 => "warm",
+            _ => "hot"
+        }
+    }
 }
 
 impl fmt::Display for Temperature {
@@ -105,7 +125,9 @@ pub struct CurrentWeather {
     /// Cloud cover 0-100%
     pub cloud_cover: u8,
     /// Wind speed / direction with units
-    pub wind: Wind
+    pub wind: Wind,
+    /// The location the weather data is for
+    pub location: Cordinates
 }
 
 impl CurrentWeather {
@@ -117,11 +139,29 @@ impl CurrentWeather {
         precipitation: Precipitation, 
         weather_code: WeatherCode, 
         cloud_cover: u8, 
-        wind: Wind
+        wind: Wind,
+        location: Cordinates
     ) -> Self {
-        CurrentWeather { temperature, apparent_temp, humidity, is_day, precipitation, weather_code, cloud_cover, wind }
+        CurrentWeather { temperature, apparent_temp, humidity, is_day, precipitation, weather_code, cloud_cover, wind, location }
+    }
+
+    /// CSS classes describing this weather: the weather code, day or night and the temperature band
+    pub fn css_classes(&self) -> [&'static str; 3] {
+        [
+            self.weather_code.css_class(),
+            if self.is_day { "day" } else { "night" },
+            self.temperature.css_class()
+        ]
     }
 
+    /// Every class css_classes() can return, so they can be removed before adding the new ones
+    pub const ALL_CSS_CLASSES: [&'static str; 14] = [
+        "weather-clear", "weather-cloudy", "weather-fog", "weather-drizzle",
+        "weather-rain", "weather-snow", "weather-thunderstorm",
+        "day", "night",
+        "freezing", "cold", "mild", "warm", "hot"
+    ];
+
     /// Creates an example struct with all the values filled in. 
     /// Intended for testing and developing purposes
     #[allow(dead_code)]
@@ -134,7 +174,8 @@ impl CurrentWeather {
             Precipitation::new(0.15, 0.12, 0.3, 0.0, Unit::Precipitation::Mm),
             WeatherCode::from_code(2).expect("Invalid WMO code provided"),
             80,
-            Wind::new(40.0, 16.0, Unit::Speed::Kmh)
+            Wind::new(40.0, 16.0, Unit::Speed::Kmh),
+            Cordinates::new(47.4979, 19.0402)
         )
         
     }
@@ -151,7 +192,8 @@ impl CurrentWeather {
             Precipitation::new(0.15, 0.12, 0.3, 0.0, Unit::Precipitation::Mm),
             WeatherCode::from_code(code).expect("Invalid WMO code provided"),
             80,
-            Wind::new(40.0, 16.0, Unit::Speed::Kmh)
+            Wind::new(40.0, 16.0, Unit::Speed::Kmh),
+            Cordinates::new(47.4979, 19.0402)
         )
     }
     
@@ -167,7 +209,8 @@ impl CurrentWeather {
             Precipitation::new(0.15, 0.12, 0.3, 0.0, Unit::Precipitation::Mm),
             WeatherCode::from_code(code).expect("Invalid WMO code provided"),
             80,
-            Wind::new(40.0, 16.0, Unit::Speed::Kmh)
+            Wind::new(40.0, 16.0, Unit::Speed::Kmh),
+            Cordinates::new(47.4979, 19.0402)
         )
         
     }
@@ -342,11 +385,24 @@ impl WeatherCode {
             },
         }
     }
+    /// CSS class for the weather condition, similar conditions share a class
+    pub fn css_class(&self) -> &'static str {
+        match self {
+            Self::Clear => "weather-clear",
+            Self::Cloudy(_) => "weather-cloudy",
+            Self::Fog { is_rime_fog: _ } => "weather-fog",
+            Self::Drizzle(_) | Self::FreezingDrizzle(_) => "weather-drizzle",
+            Self::Rain(_) | Self::FreezingRain(_) | Self::RainShowers(_) => "weather-rain",
+            Self::SnowFall(_) | Self::SnowGrains | Self::SnowShowers(_) => "weather-snow",
+            Self::Thunderstorm | Self::ThunderstormWithHail(_) => "weather-thunderstorm",
+        }
+    }
+
     /// Converts a weather code back to a string containing a utf emoji representing the weather condition
-    pub fn to_emoji(&self, is_night: bool) -> String {
+    pub fn to_emoji(&self, is_day: bool) -> String {
         match self {
             Self::Clear => {
-                if is_night {
+                if is_day {
                     "☀️".to_string()
                 } else {
                     "🌙".to_string()
@@ -374,7 +430,7 @@ impl WeatherCode {
 }
 
 // =========================================
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Cordinates {
     pub lat: f64,
     pub lng: f64
@@ -442,6 +498,8 @@ pub async fn get_current_weather(units: &Units) -> Result<CurrentWeather, Box<dy
     ].iter().map(|d| {d.to_string()}).collect();
 
     opts.current.append(&mut current_parameters);
+
+    let location = Cordinates::new(opts.location.lat, opts.location.lng);
     
     
     let res = client
@@ -480,13 +538,10 @@ pub async fn get_current_weather(units: &Units) -> Result<CurrentWeather, Box<dy
         .expect("Failed to parse json value")
     };
     
-    let is_day = {
-        res.get("is_day")
-        .expect("Missing is_day from api response")
-        .value
-        .as_i64()
-        .expect("Failed to parse json value") 
-        == 0
+    // Fall back to calculating it locally if the api didnt return is_day
+    let is_day = match res.get("is_day").and_then(|d| d.value.as_i64()) {
+        Some(d) => d != 0,
+        None => astronomy::is_day(&location, chrono::Utc::now())
     };
 
     let prec = {
@@ -562,7 +617,8 @@ pub async fn get_current_weather(units: &Units) -> Result<CurrentWeather, Box<dy
         prec, 
         weather_code, 
         cloud_cover, 
-        wind
+        wind,
+        location
     );
 
     Ok(current_weather)